@@ -1,7 +1,6 @@
 use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 
 use rand::SeedableRng;
-use rand_pcg;
 
 use rtwasm::cast;
 use rtwasm::scenes;
@@ -19,14 +18,14 @@ fn criterion_benchmark(c: &mut Criterion) {
         let mut rng = rand_pcg::Pcg64::seed_from_u64(SEED);
 
         // initialize world and camera
-        let (world, cam) = scenes::custom_scene(NX, NY);
+        let (world, lights, cam) = scenes::custom_scene(NX, NY);
 
         // initalize params
         let params = scenes::Params::new(NX, NY, NS, SEED, String::from("benchmark.png"));
 
         b.iter_batched(
             || (),
-            |_| cast(&params, &world, &cam, &mut rng, false, false),
+            |_| cast(&params, &world, &lights, &cam, &mut rng, false, false),
             BatchSize::SmallInput,
         );
     });
@@ -42,14 +41,14 @@ fn criterion_benchmark(c: &mut Criterion) {
         let mut rng = rand_pcg::Pcg64::seed_from_u64(SEED);
 
         // initialize world and camera
-        let (world, cam) = scenes::rtiow_scene(NX, NY, &mut rng);
+        let (world, lights, cam) = scenes::rtiow_scene(NX, NY, &mut rng);
 
         // initalize params
         let params = scenes::Params::new(NX, NY, NS, SEED, String::from("benchmark.png"));
 
         b.iter_batched(
             || (),
-            |_| cast(&params, &world, &cam, &mut rng, false, false),
+            |_| cast(&params, &world, &lights, &cam, &mut rng, false, false),
             BatchSize::SmallInput,
         );
     });
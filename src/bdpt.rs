@@ -0,0 +1,914 @@
+use std::f32;
+
+use crate::aabb::Aabb;
+use crate::camera::Camera;
+use crate::film::Film;
+use crate::intersect::{IntersectEvent, IntersectList, IntersectRecord};
+use crate::light::{BoundingSphere, EnvironmentLight, Light, LightList};
+use crate::material::InteriorStack;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::sampling;
+use crate::scenes::Params;
+use crate::spectrum;
+use crate::vec::{Onb, Vec3};
+use crate::MAX_WALK_STEPS;
+
+pub struct Emitters<'a> {
+    lights: &'a LightList,
+    environment: EnvironmentLight<'a>,
+    scene: BoundingSphere,
+    cdf: Vec<f32>,
+}
+
+impl<'a> Emitters<'a> {
+    pub fn new(world: &IntersectList, lights: &'a LightList, cam: &Camera) -> Emitters<'a> {
+        // the lights of the scene and its environment, picked in proportion to
+        // their power; light from lights at infinity enters through a sphere
+        // bounding the bounded objects and the camera
+        let camera = Aabb::new(cam.origin, cam.origin);
+        let bbox = match world.finite_bounding_box(cam.time_open, cam.time_close) {
+            Some(bbox) => bbox.surrounding(&camera),
+            None => camera,
+        };
+        let radius = 0.5 * (bbox.max - bbox.min).length();
+        let scene = BoundingSphere::new(
+            bbox.centroid(),
+            if radius > 0.0 { 1.01 * radius } else { 1.0 },
+        );
+        let mut emitters = Emitters {
+            lights,
+            environment: EnvironmentLight::new(&*lights.environment),
+            scene,
+            cdf: Vec::new(),
+        };
+
+        let powers: Vec<f32> = (0..emitters.count())
+            .map(|i| emitters.light(i).power(&scene).max(0.0))
+            .collect();
+        let total: f32 = powers.iter().sum();
+        let mut sum = 0.0;
+        for power in powers.iter() {
+            sum += if total > 0.0 {
+                power / total
+            } else {
+                1.0 / powers.len() as f32
+            };
+            emitters.cdf.push(sum);
+        }
+        emitters
+    }
+
+    fn count(&self) -> usize {
+        self.lights.lights.len() + 1
+    }
+
+    fn light(&self, index: usize) -> &dyn Light {
+        // the environment follows the lights of the scene
+        match self.lights.lights.get(index) {
+            Some(light) => light.as_ref(),
+            None => &self.environment,
+        }
+    }
+
+    fn pmf(&self, index: usize) -> f32 {
+        // probability of picking the light with `index`
+        match index {
+            0 => self.cdf[0],
+            _ => self.cdf[index] - self.cdf[index - 1],
+        }
+    }
+
+    fn sample(&self, u: f32) -> Option<(usize, f32)> {
+        // pick a light in proportion to its power; returns its index and the
+        // probability of picking it
+        let index = self
+            .cdf
+            .iter()
+            .position(|&c| u < c)
+            .unwrap_or(self.cdf.len() - 1);
+        let pmf = self.pmf(index);
+        if pmf <= 0.0 {
+            return None;
+        }
+        Some((index, pmf))
+    }
+
+    fn infinite_density(&self, direction: Vec3) -> f32 {
+        // density of the direction of light travelling in `direction` from
+        // any of the lights at infinity
+        (0..self.count())
+            .filter(|&i| self.light(i).bounds().is_none())
+            .map(|i| {
+                self.pmf(i)
+                    * self
+                        .light(i)
+                        .pdf_emission(self.scene.center, direction, &self.scene)
+                        .1
+            })
+            .sum()
+    }
+}
+
+struct Context<'a> {
+    world: &'a IntersectList,
+    emitters: &'a Emitters<'a>,
+    cam: &'a Camera,
+    params: &'a Params,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum VertexKind {
+    Camera,
+    Light,
+    Surface,
+    Medium,
+}
+
+struct Vertex<'a> {
+    kind: VertexKind,
+    p: Vec3,
+    // geometric normal of vertices on surfaces, zero elsewhere; the direction
+    // light travels in from vertices on lights at infinity
+    normal: Vec3,
+    record: Option<IntersectRecord<'a>>,
+    // ray along which the subpath arrived at the vertex
+    incoming: Ray,
+    interiors: InteriorStack,
+    beta: Vec3,
+    dispersed: bool,
+    delta: bool,
+    infinite: bool,
+    light: Option<usize>,
+    // number of steps of random walks through scattering interiors on the
+    // subpath up to the vertex, which do not count towards `max_depth`
+    walks: usize,
+    // densities (with respect to area, or solid angle towards lights at
+    // infinity) of sampling the vertex from its neighbors on the subpath
+    pdf_fwd: f32,
+    pdf_rev: f32,
+}
+
+impl<'a> Vertex<'a> {
+    fn endpoint(kind: VertexKind, p: Vec3, normal: Vec3, incoming: Ray, beta: Vec3) -> Vertex<'a> {
+        Vertex {
+            kind,
+            p,
+            normal,
+            record: None,
+            incoming,
+            interiors: InteriorStack::new(),
+            beta,
+            dispersed: false,
+            delta: false,
+            infinite: false,
+            light: None,
+            walks: 0,
+            pdf_fwd: 0.0,
+            pdf_rev: 0.0,
+        }
+    }
+
+    fn on_surface(&self) -> bool {
+        self.normal != Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    fn connectible(&self) -> bool {
+        // whether the vertex scatters light arriving from any direction
+        !self.delta && (self.kind == VertexKind::Surface || self.kind == VertexKind::Medium)
+    }
+
+    fn eval(&self, wi: Vec3) -> Vec3 {
+        // bsdf times |cos(theta_i)| (or phase function) for light arriving
+        // from `wi` and scattered back along the subpath; on the light subpath
+        // this is the adjoint, scattering importance arriving from `wi`
+        match &self.record {
+            Some(record) => record.material.eval(&self.incoming, record, wi),
+            None => Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn convert_density(&self, pdf: f32, next: &Vertex) -> f32 {
+        // convert a density of directions from the vertex to a density of the
+        // area about `next`
+        if next.infinite {
+            return pdf;
+        }
+        let w = next.p - self.p;
+        let distance2 = w.dot(w);
+        if distance2 == 0.0 {
+            return 0.0;
+        }
+        let mut pdf = pdf / distance2;
+        if next.on_surface() {
+            pdf *= next.normal.dot(w).abs() / distance2.sqrt();
+        }
+        pdf
+    }
+
+    fn pdf(&self, ctx: &Context, prev: Option<&Vertex>, next: &Vertex) -> f32 {
+        // density of sampling `next` from the vertex, reached from `prev`
+        let wn = next.p - self.p;
+        let pdf = match self.kind {
+            VertexKind::Light => return self.pdf_light(ctx, next),
+            VertexKind::Camera => ctx.cam.pdf_direction(self.p, wn),
+            VertexKind::Surface | VertexKind::Medium => match (&self.record, prev) {
+                (Some(record), Some(prev)) => {
+                    let incoming = Ray {
+                        origin: prev.p,
+                        direction: self.p - prev.p,
+                        ..self.incoming
+                    };
+                    record.material.pdf(&incoming, record, wn.unit())
+                }
+                _ => 0.0,
+            },
+        };
+        self.convert_density(pdf, next)
+    }
+
+    fn pdf_light(&self, ctx: &Context, next: &Vertex) -> f32 {
+        // density of sampling `next` from light leaving the vertex on a light
+        let w = next.p - self.p;
+        let distance2 = w.dot(w);
+        let w = w / distance2.sqrt();
+        let mut pdf = if self.infinite {
+            ctx.emitters.scene.pdf_emission(next.p, self.normal)
+        } else {
+            match self.light {
+                Some(index) => {
+                    let light = ctx.emitters.light(index);
+                    light.pdf_emission(self.p, w, &ctx.emitters.scene).1 / distance2
+                }
+                None => 0.0,
+            }
+        };
+        if next.on_surface() {
+            pdf *= next.normal.dot(w).abs();
+        }
+        pdf
+    }
+
+    fn pdf_light_origin(&self, ctx: &Context, next: &Vertex) -> f32 {
+        // density of sampling the vertex on a light as the origin of light
+        // travelling towards `next`
+        if self.infinite {
+            return ctx.emitters.infinite_density(self.normal);
+        }
+        match self.light {
+            Some(index) => {
+                let light = ctx.emitters.light(index);
+                let w = (next.p - self.p).unit();
+                ctx.emitters.pmf(index) * light.pdf_emission(self.p, w, &ctx.emitters.scene).0
+            }
+            None => 0.0,
+        }
+    }
+}
+
+fn random_walk<'a>(
+    ctx: &Context<'a>,
+    r: &Ray,
+    beta: Vec3,
+    pdf: f32,
+    max_vertices: usize,
+    path: &mut Vec<Vertex<'a>>,
+    sampler: &mut dyn Sampler,
+) {
+    // extend `path` by tracing `r`, sampled with density `pdf` from the last
+    // vertex of `path`, as in `color`; the densities of sampling each vertex
+    // forwards and backwards along the path are recorded; as in `color`, the
+    // steps of random walks do not count towards `max_vertices`, but a walk
+    // is terminated after `MAX_WALK_STEPS` steps
+    let zero = Vec3::new(0.0, 0.0, 0.0);
+    let mut ray = *r;
+    let mut beta = beta;
+    let mut pdf_fwd = pdf;
+    let mut interiors = InteriorStack::new();
+    let mut dispersed = false;
+    let start = beta.x.max(beta.y).max(beta.z);
+    let camera = path[0].kind == VertexKind::Camera;
+    let mut walks = 0;
+    let mut steps = 0;
+    while path.len() - walks < max_vertices {
+        let hit = ctx.world.intersect(&ray, 0.001, f32::MAX, sampler);
+
+        // free flight through the interior the path is inside of; scattering
+        // inside the interior is left to the random walk, as a vertex which no
+        // other strategy connects to
+        let length = ray.direction.length();
+        let distance = hit.as_ref().map_or(f32::MAX, |record| record.t * length);
+        let absorption = spectrum::color(interiors.absorption(), &ray);
+        let (scattering, anisotropy) = interiors.scattering();
+        let scattering = spectrum::color(scattering, &ray);
+        if scattering.x.max(scattering.y).max(scattering.z) > 0.0 {
+            let extinction = absorption + scattering;
+            let total = beta.x + beta.y + beta.z;
+            if total <= 0.0 || steps == MAX_WALK_STEPS {
+                break;
+            }
+            let probabilities = beta / total;
+            let u = sampler.get_1d();
+            let sigma = if u < probabilities.x {
+                extinction.x
+            } else if u < probabilities.x + probabilities.y {
+                extinction.y
+            } else {
+                extinction.z
+            };
+            let t = -(1.0 - sampler.get_1d()).ln() / sigma;
+            if t < distance {
+                let transmittance = (extinction * -t).exp();
+                beta *= transmittance * scattering / probabilities.dot(extinction * transmittance);
+                let direction = ray.direction / length;
+                let mut vertex = Vertex::endpoint(
+                    VertexKind::Medium,
+                    ray.origin + direction * t,
+                    zero,
+                    ray,
+                    beta,
+                );
+                vertex.interiors = interiors.clone();
+                vertex.dispersed = dispersed;
+                vertex.delta = true;
+                walks += 1;
+                steps += 1;
+                vertex.walks = walks;
+                let n = path.len();
+                path[n - 1].pdf_rev = 0.0;
+                path.push(vertex);
+
+                let (local, _) = sampling::henyey_greenstein(sampler.get_2d(), anisotropy);
+                ray = Ray {
+                    origin: ray.origin + direction * t,
+                    direction: Onb::new(direction).to_world(local),
+                    ..ray
+                };
+                pdf_fwd = 0.0;
+                continue;
+            }
+            let transmittance = (extinction * -distance).exp();
+            beta *= transmittance / probabilities.dot(transmittance);
+        } else if hit.is_some() {
+            beta *= (absorption * -distance).exp();
+        }
+
+        // camera subpaths escaping the scene end on the lights at infinity
+        let mut record = match hit {
+            Some(record) => record,
+            None => {
+                if camera {
+                    let d = ray.direction.unit();
+                    let mut vertex =
+                        Vertex::endpoint(VertexKind::Light, ray.origin + d, -d, ray, beta);
+                    vertex.dispersed = dispersed;
+                    vertex.infinite = true;
+                    vertex.walks = walks;
+                    vertex.pdf_fwd = pdf_fwd;
+                    path.push(vertex);
+                }
+                break;
+            }
+        };
+
+        // surfaces overridden by interiors of higher priority are passed
+        // through
+        let interior = record.interior();
+        if let Some(interior) = interior {
+            if !interiors.overrides(interior) {
+                if ray.direction.dot(record.normal) < 0.0 {
+                    interiors.push(interior);
+                } else {
+                    interiors.remove(interior);
+                }
+                ray.origin = record.p;
+                continue;
+            }
+            record.exterior_ior = interiors.exterior_ior(interior);
+        }
+
+        let volumetric = record.material.volumetric();
+        let mut vertex = Vertex::endpoint(
+            if volumetric {
+                VertexKind::Medium
+            } else {
+                VertexKind::Surface
+            },
+            record.p,
+            if volumetric { zero } else { record.normal },
+            ray,
+            beta,
+        );
+        vertex.interiors = interiors.clone();
+        vertex.dispersed = dispersed;
+        vertex.walks = walks;
+        steps = 0;
+        if camera && record.material.emitted(&ray, &record) != zero {
+            vertex.light = record.light;
+        }
+        vertex.pdf_fwd = path[path.len() - 1].convert_density(pdf_fwd, &vertex);
+        if path.len() + 1 - walks == max_vertices {
+            vertex.record = Some(record);
+            path.push(vertex);
+            break;
+        }
+
+        if ray.wavelength.is_some() && !dispersed && record.material.dispersive() {
+            beta = Vec3::new(3.0 * beta.x, 0.0, 0.0);
+            dispersed = true;
+        }
+        let (scattered, attenuation, delta) = match record.material.scatter(&ray, &record, sampler)
+        {
+            Some(scattered) => scattered,
+            None => {
+                vertex.record = Some(record);
+                path.push(vertex);
+                break;
+            }
+        };
+
+        // vertices scattered in directions sampled from a part of the bsdf
+        // left out of `eval` cannot be connected to; the densities of
+        // sampling across them are left zero
+        let wi = scattered.direction;
+        vertex.delta = delta;
+        let (pdf_next, pdf_prev) = if vertex.delta {
+            (0.0, 0.0)
+        } else {
+            let reverse = Ray {
+                origin: record.p + wi,
+                direction: -wi,
+                ..ray
+            };
+            (
+                record.material.pdf(&ray, &record, wi),
+                record.material.pdf(&reverse, &record, -ray.direction),
+            )
+        };
+        if let Some(interior) = interior {
+            let cos_in = ray.direction.dot(record.normal);
+            let cos_out = wi.dot(record.normal);
+            if cos_in * cos_out > 0.0 {
+                if cos_in < 0.0 {
+                    interiors.push(interior);
+                } else {
+                    interiors.remove(interior);
+                }
+            }
+        }
+        vertex.record = Some(record);
+        path.push(vertex);
+        let n = path.len();
+        path[n - 2].pdf_rev = path[n - 1].convert_density(pdf_prev, &path[n - 2]);
+
+        beta *= attenuation;
+        pdf_fwd = pdf_next;
+        ray = Ray {
+            wavelength: ray.wavelength,
+            ..scattered
+        };
+
+        // russian roulette relative to the weight the subpath started with
+        if n - walks >= ctx.params.rr_start_depth as usize {
+            let max_beta = beta.x.max(beta.y).max(beta.z) / start;
+            if max_beta < 1.0 {
+                let q = 1.0 - max_beta;
+                if sampler.get_1d() < q {
+                    break;
+                }
+                beta /= 1.0 - q;
+            }
+        }
+    }
+}
+
+fn camera_subpath<'a>(
+    ctx: &Context<'a>,
+    r: &Ray,
+    path: &mut Vec<Vertex<'a>>,
+    sampler: &mut dyn Sampler,
+) {
+    let one = Vec3::new(1.0, 1.0, 1.0);
+    path.push(Vertex::endpoint(
+        VertexKind::Camera,
+        r.origin,
+        Vec3::new(0.0, 0.0, 0.0),
+        *r,
+        one,
+    ));
+    let pdf = ctx.cam.pdf_direction(r.origin, r.direction);
+    let max_vertices = ctx.params.max_depth as usize + 2;
+    random_walk(ctx, r, one, pdf, max_vertices, path, sampler);
+}
+
+fn light_subpath<'a>(
+    ctx: &Context<'a>,
+    r: &Ray,
+    path: &mut Vec<Vertex<'a>>,
+    sampler: &mut dyn Sampler,
+) {
+    // the light subpath starts from a light picked in proportion to its
+    // power, at the time and wavelengths of the camera ray `r`
+    let (index, pmf) = match ctx.emitters.sample(sampler.get_1d()) {
+        Some(sample) => sample,
+        None => return,
+    };
+    let light = ctx.emitters.light(index);
+    let emission =
+        match light.sample_emission(sampler.get_2d(), sampler.get_2d(), &ctx.emitters.scene) {
+            Some(emission) => emission,
+            None => return,
+        };
+    let ray = Ray {
+        origin: emission.origin,
+        direction: emission.direction,
+        ..*r
+    };
+    let radiance = spectrum::color(emission.radiance, &ray);
+    if emission.pdf_position <= 0.0
+        || emission.pdf_direction <= 0.0
+        || radiance == Vec3::new(0.0, 0.0, 0.0)
+    {
+        return;
+    }
+    let infinite = light.bounds().is_none();
+    let mut vertex = Vertex::endpoint(
+        VertexKind::Light,
+        emission.origin,
+        emission.normal,
+        ray,
+        radiance,
+    );
+    vertex.infinite = infinite;
+    vertex.light = Some(index);
+    vertex.pdf_fwd = pmf * emission.pdf_position;
+    path.push(vertex);
+
+    let cos_theta = if emission.normal == Vec3::new(0.0, 0.0, 0.0) {
+        1.0
+    } else {
+        emission.normal.dot(emission.direction).abs()
+    };
+    let beta = radiance * (cos_theta / (pmf * emission.pdf_position * emission.pdf_direction));
+    let max_vertices = ctx.params.max_depth as usize + 1;
+    random_walk(
+        ctx,
+        &ray,
+        beta,
+        emission.pdf_direction,
+        max_vertices,
+        path,
+        sampler,
+    );
+
+    // light from a light at infinity is sampled by its direction and then by
+    // its origin on the disk facing it
+    if infinite {
+        if path.len() > 1 {
+            path[1].pdf_fwd = emission.pdf_position;
+            if path[1].on_surface() {
+                path[1].pdf_fwd *= path[1].normal.dot(emission.direction).abs();
+            }
+        }
+        path[0].pdf_fwd = ctx.emitters.infinite_density(emission.direction);
+    }
+}
+
+fn transmittance(
+    ctx: &Context,
+    from: &Vertex,
+    direction: Vec3,
+    distance: f32,
+    sampler: &mut dyn Sampler,
+) -> Vec3 {
+    // fraction of light passing along the segment leaving `from` in unit
+    // `direction`, through the objects in between and the interior on the
+    // side of `from` the segment leaves on; the segment stops short of
+    // surfaces at its end
+    let shadow = Ray {
+        origin: from.p,
+        direction,
+        ..from.incoming
+    };
+    let geometric = ctx
+        .world
+        .transmittance(&shadow, 0.001, distance * (1.0 - 1e-3), sampler);
+    if geometric <= 0.0 {
+        return Vec3::new(0.0, 0.0, 0.0);
+    }
+
+    let interior = from
+        .record
+        .as_ref()
+        .and_then(|record| Some((record.interior()?, record.normal)));
+    let crossed;
+    let interiors = match interior {
+        Some((interior, normal)) => {
+            let cos_in = from.incoming.direction.dot(normal);
+            let cos_out = direction.dot(normal);
+            if cos_in * cos_out > 0.0 {
+                let mut interiors = from.interiors.clone();
+                if cos_in < 0.0 {
+                    interiors.push(interior);
+                } else {
+                    interiors.remove(interior);
+                }
+                crossed = interiors;
+                &crossed
+            } else {
+                &from.interiors
+            }
+        }
+        None => &from.interiors,
+    };
+    let (scattering, _) = interiors.scattering();
+    let extinction = spectrum::color(interiors.absorption() + scattering, &shadow);
+    (extinction * -distance).exp() * geometric
+}
+
+fn mis_weight(
+    ctx: &Context,
+    light: &[Vertex],
+    camera: &[Vertex],
+    sampled: Option<&Vertex>,
+    s: usize,
+    t: usize,
+) -> f32 {
+    // balance heuristic weight of the path joining `s` vertices of the light
+    // subpath and `t` vertices of the camera subpath, where the endpoint of
+    // the shorter subpath may have been replaced by the vertex `sampled`;
+    // the densities with which the other strategies would sample the path
+    // follow from the densities of sampling each vertex forwards and
+    // backwards, after updating those at the join (Veach, "Robust Monte
+    // Carlo Methods for Light Transport Simulation", chapter 10)
+    if s + t == 2 {
+        return 1.0;
+    }
+    let qs = match s {
+        0 => None,
+        1 => sampled,
+        _ => Some(&light[s - 1]),
+    };
+    let pt = match t {
+        1 => sampled.expect("camera vertex sampled for t = 1"),
+        _ => &camera[t - 1],
+    };
+    let qs_minus = if s > 1 { Some(&light[s - 2]) } else { None };
+    let pt_minus = if t > 1 { Some(&camera[t - 2]) } else { None };
+
+    // forward and reverse densities of each vertex and whether it is
+    // degenerate; the vertices at the join are not
+    let pdfs = |v: &Vertex| (v.pdf_fwd, v.pdf_rev, v.delta);
+    let mut cam: Vec<(f32, f32, bool)> = camera[..t].iter().map(pdfs).collect();
+    let mut lig: Vec<(f32, f32, bool)> = light[..s].iter().map(pdfs).collect();
+    if let (1, Some(qs)) = (s, qs) {
+        lig[0] = pdfs(qs);
+    }
+    cam[t - 1].2 = false;
+    if s > 0 {
+        lig[s - 1].2 = false;
+    }
+    cam[t - 1].1 = match (qs, pt_minus) {
+        (Some(qs), _) => qs.pdf(ctx, qs_minus, pt),
+        (None, Some(pt_minus)) => pt.pdf_light_origin(ctx, pt_minus),
+        (None, None) => 0.0,
+    };
+    if let Some(pt_minus) = pt_minus {
+        cam[t - 2].1 = match qs {
+            Some(qs) => pt.pdf(ctx, Some(qs), pt_minus),
+            None => pt.pdf_light(ctx, pt_minus),
+        };
+    }
+    if let Some(qs) = qs {
+        lig[s - 1].1 = pt.pdf(ctx, pt_minus, qs);
+        if let Some(qs_minus) = qs_minus {
+            lig[s - 2].1 = qs.pdf(ctx, Some(pt), qs_minus);
+        }
+    }
+
+    // densities left zero across degenerate vertices do not count; other zero
+    // densities make the strategies beyond them impossible
+    let ratio = |pdf: (f32, f32, bool), degenerate: bool| {
+        let rev = if pdf.1 == 0.0 && degenerate {
+            1.0
+        } else {
+            pdf.1
+        };
+        let fwd = if pdf.0 == 0.0 { 1.0 } else { pdf.0 };
+        rev / fwd
+    };
+    let mut sum = 0.0;
+    let mut ri = 1.0;
+    for i in (1..t).rev() {
+        ri *= ratio(cam[i], cam[i].2 || (i + 1 < t && cam[i + 1].2));
+        if !cam[i].2 && !cam[i - 1].2 {
+            sum += ri;
+        }
+    }
+    let delta_light = match (s, qs) {
+        (1, Some(qs)) => qs.light,
+        _ => light.first().and_then(|v| v.light),
+    }
+    .is_some_and(|index| ctx.emitters.light(index).delta());
+    ri = 1.0;
+    for i in (0..s).rev() {
+        ri *= ratio(lig[i], lig[i].2 || (i + 1 < s && lig[i + 1].2));
+        let degenerate = if i > 0 { lig[i - 1].2 } else { delta_light };
+        if !lig[i].2 && !degenerate {
+            sum += ri;
+        }
+    }
+    1.0 / (1.0 + sum)
+}
+
+fn connect(
+    ctx: &Context,
+    light: &[Vertex],
+    camera: &[Vertex],
+    s: usize,
+    t: usize,
+    sampler: &mut dyn Sampler,
+    film: &mut Film,
+) -> Vec3 {
+    // contribution of the path joining `s` vertices of the light subpath and
+    // `t` vertices of the camera subpath, weighted by its mis weight; paths
+    // joined to the camera (t = 1) are splatted onto `film` instead
+    let zero = Vec3::new(0.0, 0.0, 0.0);
+    if s == 0 {
+        // the camera subpath reaches a light
+        let pt = &camera[t - 1];
+        let emitted = if pt.infinite {
+            let sky = ctx.emitters.lights.escaped(-pt.normal, true);
+            spectrum::color(sky, &pt.incoming)
+        } else {
+            match &pt.record {
+                Some(record) => record.material.emitted(&pt.incoming, record),
+                None => zero,
+            }
+        };
+        let value = pt.beta * emitted;
+        if value == zero {
+            return zero;
+        }
+        // light emitted by surfaces which are not lights is only found here
+        if !pt.infinite && pt.light.is_none() {
+            return value;
+        }
+        return value * mis_weight(ctx, light, camera, None, s, t);
+    }
+
+    if t == 1 {
+        // the light subpath is seen by the camera through a point sampled on
+        // the lens
+        let qs = &light[s - 1];
+        if !qs.connectible() {
+            return zero;
+        }
+        let lens = ctx.cam.sample_lens(sampler);
+        let offset = lens - qs.p;
+        let distance = offset.length();
+        let w = offset / distance;
+        let (u, v) = match ctx.cam.film_position(lens, -w) {
+            Some(position) => position,
+            None => return zero,
+        };
+        let importance =
+            ctx.cam.importance(-w) * ctx.cam.forward().dot(w).abs() * ctx.cam.lens_area()
+                / (distance * distance);
+        let mut sampled = Vertex::endpoint(
+            VertexKind::Camera,
+            lens,
+            ctx.cam.forward(),
+            Ray {
+                origin: qs.p,
+                direction: offset,
+                ..qs.incoming
+            },
+            Vec3::new(importance, importance, importance),
+        );
+        sampled.pdf_fwd = 0.0;
+        let mut value = qs.beta * qs.eval(w) * sampled.beta;
+        if value == zero {
+            return zero;
+        }
+        value *= transmittance(ctx, qs, w, distance, sampler);
+        if value == zero {
+            return zero;
+        }
+        value *= mis_weight(ctx, light, camera, Some(&sampled), s, t);
+        let (x, y) = (u * ctx.params.nx as f32, v * ctx.params.ny as f32);
+        match qs.incoming.wavelength {
+            Some(hero) => film.add_spectral_splat(x, y, value, spectrum::wavelengths(hero)),
+            None => film.add_splat(x, y, value),
+        }
+        return zero;
+    }
+
+    let pt = &camera[t - 1];
+    if !pt.connectible() {
+        return zero;
+    }
+    if s == 1 {
+        // the camera subpath is connected to a point sampled on a light
+        let (index, pmf) = match ctx.emitters.sample(sampler.get_1d()) {
+            Some(sample) => sample,
+            None => return zero,
+        };
+        let light_ = ctx.emitters.light(index);
+        let sample = match light_.sample(pt.p, sampler.get_2d()) {
+            Some(sample) => sample,
+            None => return zero,
+        };
+        let infinite = light_.bounds().is_none();
+        let radiance = spectrum::color(sample.radiance, &pt.incoming) / pmf;
+        let mut sampled = Vertex::endpoint(
+            VertexKind::Light,
+            if infinite {
+                pt.p + sample.wi
+            } else {
+                pt.p + sample.wi * sample.distance
+            },
+            if infinite { -sample.wi } else { sample.normal },
+            pt.incoming,
+            radiance,
+        );
+        sampled.infinite = infinite;
+        sampled.light = Some(index);
+        sampled.delta = light_.delta();
+        sampled.pdf_fwd = sampled.pdf_light_origin(ctx, pt);
+        let mut value = pt.beta * pt.eval(sample.wi) * radiance;
+        if value == zero {
+            return zero;
+        }
+        value *= transmittance(ctx, pt, sample.wi, sample.distance, sampler);
+        if value == zero {
+            return zero;
+        }
+        return value * mis_weight(ctx, light, camera, Some(&sampled), s, t);
+    }
+
+    // the subpaths are joined by a segment between their last vertices
+    let qs = &light[s - 1];
+    if !qs.connectible() {
+        return zero;
+    }
+    let offset = qs.p - pt.p;
+    let distance2 = offset.dot(offset);
+    let distance = distance2.sqrt();
+    let w = offset / distance;
+    let mut value = qs.beta * qs.eval(-w) * pt.eval(w) * pt.beta / distance2;
+    if qs.dispersed && pt.dispersed {
+        // both subpaths carry the estimate for all wavelengths
+        value /= 3.0;
+    }
+    if value == zero {
+        return zero;
+    }
+    value *= transmittance(ctx, pt, w, distance, sampler);
+    if value == zero {
+        return zero;
+    }
+    value * mis_weight(ctx, light, camera, None, s, t)
+}
+
+pub fn color(
+    r: &Ray,
+    world: &IntersectList,
+    emitters: &Emitters,
+    cam: &Camera,
+    params: &Params,
+    sampler: &mut dyn Sampler,
+    film: &mut Film,
+) -> Vec3 {
+    // bidirectional path tracing: trace a subpath from the camera along `r`
+    // and a subpath from a light, and join every prefix of one with every
+    // prefix of the other, combining the strategies by multiple importance
+    // sampling; returns the radiance along `r` and splats the paths seen by
+    // the camera from the light subpath onto `film`
+    let ctx = Context {
+        world,
+        emitters,
+        cam,
+        params,
+    };
+    let mut camera = Vec::with_capacity(params.max_depth as usize + 2);
+    camera_subpath(&ctx, r, &mut camera, sampler);
+    let mut light = Vec::with_capacity(params.max_depth as usize + 1);
+    light_subpath(&ctx, r, &mut light, sampler);
+
+    let mut radiance = Vec3::new(0.0, 0.0, 0.0);
+    for t in 1..=camera.len() {
+        for s in 0..=light.len() {
+            if (s == 1 && t == 1) || s + t < 2 {
+                continue;
+            }
+            // the steps of random walks do not count towards the depth
+            let walks = light[..s].last().map_or(0, |v| v.walks) + camera[t - 1].walks;
+            if s + t - 2 - walks > params.max_depth as usize {
+                continue;
+            }
+            radiance += connect(&ctx, &light, &camera, s, t, sampler, film);
+        }
+    }
+    radiance
+}
@@ -0,0 +1,130 @@
+use std::cmp::Ordering;
+
+use crate::aabb::Aabb;
+use crate::intersect::{IntersectEvent, IntersectRecord};
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+
+enum BvhNode {
+    // object and its index in the list the hierarchy was built over
+    Leaf(Box<dyn IntersectEvent>, usize),
+    Branch(Box<Bvh>, Box<Bvh>),
+}
+
+pub struct Bvh {
+    bbox: Aabb,
+    node: BvhNode,
+    // number of objects in the list the hierarchy was built over
+    count: usize,
+}
+
+impl Bvh {
+    pub fn new(list: Vec<Box<dyn IntersectEvent>>, t0: f32, t1: f32) -> Bvh {
+        // construct a bounding volume hierarchy over `list`; every object must be
+        // bounded over the shutter interval [`t0`, `t1`]
+        let count = list.len();
+        let items = list
+            .into_iter()
+            .enumerate()
+            .map(|(index, object)| {
+                let bbox = object
+                    .bounding_box(t0, t1)
+                    .expect("object in bvh has no bounding box");
+                (bbox, index, object)
+            })
+            .collect();
+        Bvh::build(items, count)
+    }
+
+    fn build(mut items: Vec<(Aabb, usize, Box<dyn IntersectEvent>)>, count: usize) -> Bvh {
+        // split along the longest axis of the centroid bounds at the median
+        assert!(!items.is_empty(), "bvh requires at least one object");
+        if items.len() == 1 {
+            let (bbox, index, object) = items.pop().unwrap();
+            return Bvh {
+                bbox,
+                node: BvhNode::Leaf(object, index),
+                count,
+            };
+        }
+        let centroids = items
+            .iter()
+            .map(|(bbox, _, _)| {
+                let c = bbox.centroid();
+                Aabb::new(c, c)
+            })
+            .fold(None, |acc: Option<Aabb>, b| match acc {
+                Some(a) => Some(a.surrounding(&b)),
+                None => Some(b),
+            })
+            .unwrap();
+        let extent = centroids.max - centroids.min;
+        let axis = if extent.x > extent.y && extent.x > extent.z {
+            0
+        } else if extent.y > extent.z {
+            1
+        } else {
+            2
+        };
+        let key = |bbox: &Aabb| {
+            let c = bbox.centroid();
+            [c.x, c.y, c.z][axis]
+        };
+        items.sort_by(|a, b| key(&a.0).partial_cmp(&key(&b.0)).unwrap_or(Ordering::Equal));
+        let right_items = items.split_off(items.len() / 2);
+        let left = Bvh::build(items, count);
+        let right = Bvh::build(right_items, count);
+        Bvh {
+            bbox: left.bbox.surrounding(&right.bbox),
+            node: BvhNode::Branch(Box::new(left), Box::new(right)),
+            count,
+        }
+    }
+}
+
+impl IntersectEvent for Bvh {
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
+        if !self.bbox.hit(r, t_min, t_max) {
+            return None;
+        }
+        match &self.node {
+            BvhNode::Leaf(object, index) => object
+                .intersect(r, t_min, t_max, sampler)
+                .map(|record| record.within(*index, self.count)),
+            BvhNode::Branch(left, right) => {
+                let left_record = left.intersect(r, t_min, t_max, sampler);
+                let t_nearest = left_record.as_ref().map_or(t_max, |record| record.t);
+                match right.intersect(r, t_min, t_nearest, sampler) {
+                    Some(record) => Some(record),
+                    None => left_record,
+                }
+            }
+        }
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+
+    fn transmittance(&self, r: &Ray, t_min: f32, t_max: f32, sampler: &mut dyn Sampler) -> f32 {
+        if !self.bbox.hit(r, t_min, t_max) {
+            return 1.0;
+        }
+        match &self.node {
+            BvhNode::Leaf(object, _) => object.transmittance(r, t_min, t_max, sampler),
+            BvhNode::Branch(left, right) => {
+                let transmittance = left.transmittance(r, t_min, t_max, sampler);
+                if transmittance <= 0.0 {
+                    return 0.0;
+                }
+                transmittance * right.transmittance(r, t_min, t_max, sampler)
+            }
+        }
+    }
+}
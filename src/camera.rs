@@ -1,22 +1,14 @@
 use std::f32;
 
-use rand::Rng;
-use rand_pcg;
-
 use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::sampling;
 use crate::vec::Vec3;
 
-pub fn random_point_in_disk(rng: &mut rand_pcg::Pcg64) -> Vec3 {
+pub fn random_point_in_disk(sampler: &mut dyn Sampler) -> Vec3 {
     // return a random point inside the unit circle
-    let unit = Vec3::new(1.0, 1.0, 0.0);
-    loop {
-        // select random points inside the unit square until a selected point
-        // is contained inside the unit circle
-        let p = Vec3::new(rng.gen::<f32>(), rng.gen::<f32>(), 0.0) * 2.0 - unit;
-        if p.squared_length() < 1.0 {
-            return p;
-        }
-    }
+    let (p, _) = sampling::concentric_disk(sampler.get_2d());
+    p
 }
 
 pub struct Camera {
@@ -27,6 +19,8 @@ pub struct Camera {
     pub u: Vec3,
     pub v: Vec3,
     pub radius: f32,
+    pub time_open: f32,
+    pub time_close: f32,
 }
 
 impl Camera {
@@ -55,7 +49,7 @@ impl Camera {
         let u = vup.cross(w).unit();
         let v = w.cross(u);
         Camera {
-            origin: origin,
+            origin,
             corner: origin
                 - v * half_height * focus_dist
                 - u * half_width * focus_dist
@@ -65,16 +59,94 @@ impl Camera {
             u,
             v,
             radius: aperture / 2.0,
+            time_open: 0.0,
+            time_close: 0.0,
         }
     }
 
-    pub fn point(&self, s: f32, t: f32, rng: &mut rand_pcg::Pcg64) -> Ray {
+    pub fn with_shutter(mut self, time_open: f32, time_close: f32) -> Camera {
+        // open the shutter over [time_open, time_close]; rays are traced at
+        // times uniformly distributed over the interval
+        self.time_open = time_open;
+        self.time_close = time_close;
+        self
+    }
+
+    pub fn point(&self, s: f32, t: f32, sampler: &mut dyn Sampler) -> Ray {
         // return ray from the origin to coordinate (s, t)
-        let rd = random_point_in_disk(rng) * self.radius;
+        let rd = random_point_in_disk(sampler) * self.radius;
         let offset = self.u * rd.x + self.v * rd.y;
+        let time = self.time_open + sampler.get_1d() * (self.time_close - self.time_open);
         Ray::new(
             self.origin + offset,
             self.corner + self.horizontal * s + self.vertical * t - self.origin - offset,
+            time,
         )
     }
+
+    pub fn sample_lens(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        // return a point on the lens sampled uniformly over its area
+        let rd = random_point_in_disk(sampler) * self.radius;
+        self.origin + self.u * rd.x + self.v * rd.y
+    }
+
+    pub fn lens_area(&self) -> f32 {
+        // area of the lens; a pinhole is taken to have unit area
+        if self.radius > 0.0 {
+            f32::consts::PI * self.radius * self.radius
+        } else {
+            1.0
+        }
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        // viewing direction, normal to the lens
+        self.v.cross(self.u)
+    }
+
+    fn film_area(&self) -> f32 {
+        // area of the film projected onto the plane at unit distance
+        let focus_dist = (self.corner + (self.horizontal + self.vertical) * 0.5 - self.origin)
+            .dot(self.forward());
+        self.horizontal.length() * self.vertical.length() / (focus_dist * focus_dist)
+    }
+
+    pub fn film_position(&self, origin: Vec3, direction: Vec3) -> Option<(f32, f32)> {
+        // return the coordinate (s, t) seen by the ray leaving the point
+        // `origin` on the lens in `direction`, or `None` if it is not seen
+        let forward = self.forward();
+        let cos_theta = direction.unit().dot(forward);
+        if cos_theta <= 0.0 {
+            return None;
+        }
+        let focus_dist =
+            (self.corner + (self.horizontal + self.vertical) * 0.5 - self.origin).dot(forward);
+        let focus = origin + direction.unit() * (focus_dist / cos_theta) - self.corner;
+        let s = focus.dot(self.u) / self.horizontal.length();
+        let t = focus.dot(self.v) / self.vertical.length();
+        if !(0.0..1.0).contains(&s) || !(0.0..1.0).contains(&t) {
+            return None;
+        }
+        Some((s, t))
+    }
+
+    pub fn importance(&self, direction: Vec3) -> f32 {
+        // importance emitted by the camera along rays leaving the lens in
+        // `direction` towards the film, normalized over the film and lens
+        let cos_theta = direction.unit().dot(self.forward());
+        if cos_theta <= 0.0 {
+            return 0.0;
+        }
+        1.0 / (self.film_area() * self.lens_area() * cos_theta.powi(4))
+    }
+
+    pub fn pdf_direction(&self, origin: Vec3, direction: Vec3) -> f32 {
+        // density (with respect to solid angle) with which `point` samples
+        // rays leaving the point `origin` on the lens in `direction`
+        if self.film_position(origin, direction).is_none() {
+            return 0.0;
+        }
+        let cos_theta = direction.unit().dot(self.forward());
+        1.0 / (self.film_area() * cos_theta.powi(3))
+    }
 }
@@ -0,0 +1,225 @@
+use std::f32;
+use std::str::FromStr;
+
+use crate::spectrum;
+use crate::vec::Vec3;
+
+fn sinc(x: f32) -> f32 {
+    // normalized sinc function
+    if x.abs() < 1e-5 {
+        return 1.0;
+    }
+    let px = f32::consts::PI * x;
+    px.sin() / px
+}
+
+fn mitchell(x: f32) -> f32 {
+    // mitchell-netravali cubic with B = C = 1/3, supported on [-2, 2]
+    const B: f32 = 1.0 / 3.0;
+    const C: f32 = 1.0 / 3.0;
+    let x = x.abs();
+    if x < 1.0 {
+        ((12.0 - 9.0 * B - 6.0 * C) * x * x * x
+            + (-18.0 + 12.0 * B + 6.0 * C) * x * x
+            + (6.0 - 2.0 * B))
+            / 6.0
+    } else if x < 2.0 {
+        ((-B - 6.0 * C) * x * x * x
+            + (6.0 * B + 30.0 * C) * x * x
+            + (-12.0 * B - 48.0 * C) * x
+            + (8.0 * B + 24.0 * C))
+            / 6.0
+    } else {
+        0.0
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum FilterType {
+    Box,
+    Tent,
+    Gaussian,
+    Mitchell,
+    Lanczos,
+}
+
+impl FilterType {
+    pub fn default_radius(self) -> f32 {
+        // radius used when no radius is configured
+        match self {
+            FilterType::Box => 0.5,
+            FilterType::Tent => 1.0,
+            FilterType::Gaussian => 1.5,
+            FilterType::Mitchell => 2.0,
+            FilterType::Lanczos => 3.0,
+        }
+    }
+}
+
+impl FromStr for FilterType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<FilterType, String> {
+        match s {
+            "box" => Ok(FilterType::Box),
+            "tent" => Ok(FilterType::Tent),
+            "gaussian" => Ok(FilterType::Gaussian),
+            "mitchell" => Ok(FilterType::Mitchell),
+            "lanczos" => Ok(FilterType::Lanczos),
+            _ => Err(format!("unknown filter: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Filter {
+    pub kind: FilterType,
+    pub radius: f32,
+}
+
+impl Filter {
+    pub fn new(kind: FilterType, radius: f32) -> Filter {
+        // construct a separable reconstruction filter
+        //   :kind:   filter function
+        //   :radius: filter extent (in pixels) along each axis
+        Filter { kind, radius }
+    }
+
+    fn evaluate_1d(&self, x: f32) -> f32 {
+        // evaluate the filter at offset `x` from the pixel center
+        let r = self.radius;
+        if x.abs() >= r {
+            return 0.0;
+        }
+        match self.kind {
+            FilterType::Box => 1.0,
+            FilterType::Tent => r - x.abs(),
+            FilterType::Gaussian => {
+                // gaussian shifted to reach zero at the filter radius
+                let alpha = 2.0 / (r * r);
+                (-alpha * x * x).exp() - (-alpha * r * r).exp()
+            }
+            FilterType::Mitchell => mitchell(2.0 * x / r),
+            FilterType::Lanczos => sinc(x) * sinc(x / r),
+        }
+    }
+
+    pub fn evaluate(&self, x: f32, y: f32) -> f32 {
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+}
+
+// smallest total filter weight of a pixel, relative to the sum of the
+// magnitudes of its weights, that the pixel is normalized by
+const MIN_RELATIVE_WEIGHT: f32 = 1e-3;
+
+pub struct Film {
+    pub nx: u32,
+    pub ny: u32,
+    pub filter: Filter,
+    pixels: Vec<Vec3>,
+    weights: Vec<f32>,
+    // sum of the magnitudes of the weights, against which the cancellation
+    // of negative lobes in `weights` is measured
+    magnitudes: Vec<f32>,
+    splats: Vec<Vec3>,
+    splat_scale: f32,
+}
+
+impl Film {
+    pub fn new(nx: u32, ny: u32, filter: Filter) -> Film {
+        // construct film of `nx` by `ny` pixels; pixel (i, j) covers
+        // [i, i + 1) x [j, j + 1) with `j` increasing upwards
+        Film {
+            nx,
+            ny,
+            filter,
+            pixels: vec![Vec3::new(0.0, 0.0, 0.0); (nx * ny) as usize],
+            weights: vec![0.0; (nx * ny) as usize],
+            magnitudes: vec![0.0; (nx * ny) as usize],
+            splats: vec![Vec3::new(0.0, 0.0, 0.0); (nx * ny) as usize],
+            splat_scale: 1.0,
+        }
+    }
+
+    pub fn with_splat_scale(mut self, splat_scale: f32) -> Film {
+        // scale applied to the sum of the splats in each pixel, typically the
+        // reciprocal of the number of samples per pixel
+        self.splat_scale = splat_scale;
+        self
+    }
+
+    pub fn add_sample(&mut self, x: f32, y: f32, value: Vec3) {
+        // splat a radiance sample at film position (`x`, `y`) into every pixel
+        // whose filter support contains it
+        let r = self.filter.radius;
+        let i0 = ((x - 0.5 - r).floor() as i64 + 1).max(0);
+        let i1 = ((x - 0.5 + r).floor() as i64).min(self.nx as i64 - 1);
+        let j0 = ((y - 0.5 - r).floor() as i64 + 1).max(0);
+        let j1 = ((y - 0.5 + r).floor() as i64).min(self.ny as i64 - 1);
+        for j in j0..=j1 {
+            for i in i0..=i1 {
+                let weight = self
+                    .filter
+                    .evaluate(x - (i as f32 + 0.5), y - (j as f32 + 0.5));
+                if weight != 0.0 {
+                    let idx = (j as u32 * self.nx + i as u32) as usize;
+                    self.pixels[idx] += value * weight;
+                    self.weights[idx] += weight;
+                    self.magnitudes[idx] += weight.abs();
+                }
+            }
+        }
+    }
+
+    pub fn add_spectral_sample(&mut self, x: f32, y: f32, values: Vec3, wavelengths: Vec3) {
+        // splat a spectral radiance sample, given by its `values` at
+        // `wavelengths`, after converting it to XYZ and then to sRGB
+        self.add_sample(x, y, spectrum::to_rgb(values, wavelengths));
+    }
+
+    pub fn add_splat(&mut self, x: f32, y: f32, value: Vec3) {
+        // add a radiance sample at film position (`x`, `y`) to the pixel
+        // containing it without filtering or normalization, for samples
+        // which reach the film from the scene rather than being taken at
+        // each pixel
+        if x < 0.0 || y < 0.0 || x >= self.nx as f32 || y >= self.ny as f32 {
+            return;
+        }
+        let idx = (y as u32 * self.nx + x as u32) as usize;
+        self.splats[idx] += value;
+    }
+
+    pub fn add_spectral_splat(&mut self, x: f32, y: f32, values: Vec3, wavelengths: Vec3) {
+        self.add_splat(x, y, spectrum::to_rgb(values, wavelengths));
+    }
+
+    pub fn pixel(&self, i: u32, j: u32) -> Vec3 {
+        // return the reconstructed value of pixel (i, j)
+        let idx = (j * self.nx + i) as usize;
+        let splat = self.splats[idx] * self.splat_scale;
+        // filters with negative lobes can leave a vanishing or negative total
+        // weight, e.g. at the border of the film, and dividing by it would
+        // blow up into speckles; the pixel is then left to the splats if the
+        // total is below a small fraction of the weights it cancelled out of
+        if self.weights[idx] <= MIN_RELATIVE_WEIGHT * self.magnitudes[idx] {
+            return splat;
+        }
+        self.pixels[idx] / self.weights[idx] + splat
+    }
+
+    pub fn save(&self, output: &str) {
+        // gamma correct and write the film to `output`, top row first
+        let mut image = Vec::with_capacity((3 * self.nx * self.ny) as usize);
+        for j in (0..self.ny).rev() {
+            for i in 0..self.nx {
+                let pixel = self.pixel(i, j);
+                image.push((255.99 * pixel.x.max(0.0).sqrt()) as u8);
+                image.push((255.99 * pixel.y.max(0.0).sqrt()) as u8);
+                image.push((255.99 * pixel.z.max(0.0).sqrt()) as u8);
+            }
+        }
+        image::save_buffer(output, &image, self.nx, self.ny, image::RGB(8))
+            .expect("error saving image");
+    }
+}
@@ -0,0 +1,125 @@
+use std::rc::Rc;
+
+use crate::aabb::Aabb;
+use crate::intersect::{IntersectEvent, IntersectRecord};
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vec::{AnimatedTransform, Transform, Vec3};
+
+// number of intervals at which animated bounds are evaluated between keyframes
+const MOTION_STEPS: u32 = 32;
+
+pub struct Instance {
+    pub object: Rc<dyn IntersectEvent>,
+    pub transform: Transform,
+    pub motion: Option<AnimatedTransform>,
+}
+
+impl Instance {
+    pub fn new(object: Rc<dyn IntersectEvent>, transform: Transform) -> Instance {
+        // place the shared `object` into the world with `transform`
+        //   :object:    intersectable object (or list of objects) in object space
+        //   :transform: object space to world space transform
+        Instance {
+            object,
+            transform,
+            motion: None,
+        }
+    }
+
+    pub fn animated(
+        object: Rc<dyn IntersectEvent>,
+        transform: Transform,
+        motion: AnimatedTransform,
+    ) -> Instance {
+        // place the shared `object` into the world with `transform` followed
+        // by the time-varying `motion`
+        Instance {
+            object,
+            transform,
+            motion: Some(motion),
+        }
+    }
+
+    pub fn transform_at(&self, time: f32) -> Transform {
+        // return the object space to world space transform at `time`
+        match &self.motion {
+            Some(motion) => motion.keyframe(time).transform() * self.transform,
+            None => self.transform,
+        }
+    }
+}
+
+impl IntersectEvent for Instance {
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
+        // intersect `r` in object space; the direction is not normalized so
+        // timesteps are identical in both spaces
+        let transform = self.transform_at(r.time);
+        let to_object = transform.inverse();
+        let mut local = *r;
+        local.origin = to_object.point(r.origin);
+        local.direction = to_object.vector(r.direction);
+        self.object
+            .intersect(&local, t_min, t_max, sampler)
+            .map(|mut record| {
+                record.p = transform.point(record.p);
+                record.normal = transform.normal(record.normal).unit();
+                record.tangent = transform.vector(record.tangent);
+                record
+            })
+    }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+        let object_bbox = self.object.bounding_box(t0, t1)?;
+        let corners = object_bbox.corners();
+        let bound = |transform: &Transform| {
+            let world = corners.iter().map(|&c| transform.point(c));
+            world.fold(None, |bbox: Option<Aabb>, p| {
+                let point = Aabb::new(p, p);
+                Some(bbox.map_or(point, |bbox| bbox.surrounding(&point)))
+            })
+        };
+        let motion = match &self.motion {
+            Some(motion) => motion,
+            None => return bound(&self.transform),
+        };
+
+        // bound the transformed corners at the interval ends, at every keyframe
+        // inside the interval, and at regular steps in between
+        let mut times = vec![t0, t1];
+        times.extend(
+            motion
+                .keyframes
+                .iter()
+                .map(|k| k.time)
+                .filter(|&t| t0 < t && t < t1),
+        );
+        times.extend((1..MOTION_STEPS).map(|i| t0 + (t1 - t0) * i as f32 / MOTION_STEPS as f32));
+        times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let mut bbox = bound(&self.transform_at(t0))?;
+        let mut radius: f32 = 0.0;
+        let mut max_angle: f32 = 0.0;
+        for pair in times.windows(2) {
+            let (a, b) = (motion.keyframe(pair[0]), motion.keyframe(pair[1]));
+            let transform = self.transform_at(pair[1]);
+            bbox = bbox.surrounding(&bound(&transform)?);
+            for &c in corners.iter() {
+                radius = radius.max((transform.point(c) - b.translate).length());
+            }
+            max_angle = max_angle.max(2.0 * a.rotate.dot(b.rotate).abs().min(1.0).acos());
+        }
+
+        // corners travel along arcs between the sampled times; pad by the
+        // largest distance between an arc and its chord
+        let pad = radius * (1.0 - (max_angle / 2.0).cos());
+        let pad = Vec3::new(pad, pad, pad);
+        Some(Aabb::new(bbox.min - pad, bbox.max + pad))
+    }
+}
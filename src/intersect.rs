@@ -1,32 +1,106 @@
-use crate::material::Material;
+use crate::aabb::Aabb;
+use crate::material::{Interior, Material};
 use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::texture::Texture;
 use crate::vec::Vec3;
 
 pub struct IntersectRecord<'a> {
     pub t: f32,
     pub p: Vec3,
     pub normal: Vec3,
+    pub u: f32,
+    pub v: f32,
+    pub tangent: Vec3,
     pub material: &'a dyn Material,
+    pub object: usize,
+    pub light: Option<usize>,
+    pub exterior_ior: f32,
 }
 
 impl<'a> IntersectRecord<'a> {
-    pub fn new(t: f32, p: Vec3, normal: Vec3, material: &'a dyn Material) -> IntersectRecord {
+    pub fn new(
+        t: f32,
+        p: Vec3,
+        normal: Vec3,
+        u: f32,
+        v: f32,
+        material: &'a dyn Material,
+    ) -> IntersectRecord<'a> {
         // construct intersection record
         //   :t:        timestep of intersection
         //   :p:        intersection point
         //   :normal:   surface normal at intersection point
+        //   :u:        surface coordinate at intersection point
+        //   :v:        surface coordinate at intersection point
         //   :material: surface material
+        // the object hit is identified by the enclosing lists as they return
+        // the record, see `IntersectRecord::within`; the surface is not that
+        // of a light unless tagged by an enclosing `LightSurface`, and the
+        // refractive index of the medium on the side of the normal is vacuum
+        // until set by the integrator
         IntersectRecord {
             t,
             p,
             normal,
+            u,
+            v,
+            tangent: Vec3::new(0.0, 0.0, 0.0),
             material,
+            object: 0,
+            light: None,
+            exterior_ior: 1.0,
         }
     }
+
+    pub fn with_tangent(mut self, tangent: Vec3) -> IntersectRecord<'a> {
+        // orient the shading frame along the derivative of the surface point
+        // with respect to `u`; without a tangent the frame about the normal
+        // is arbitrary
+        self.tangent = tangent;
+        self
+    }
+
+    pub fn within(mut self, index: usize, count: usize) -> IntersectRecord<'a> {
+        // identify the object hit as the `index`-th of the `count` objects of
+        // an enclosing list, within which it was identified by `object`; the
+        // indices are assigned as the scene is built, so that the objects
+        // along distinct paths through nested lists and instances are distinct
+        self.object = self.object * count + index;
+        self
+    }
+
+    pub fn interior(&self) -> Option<Interior> {
+        // interior of the material, identified by the object hit
+        self.material.interior().map(|interior| Interior {
+            id: self.object,
+            ..interior
+        })
+    }
 }
 
 pub trait IntersectEvent {
-    fn intersect(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<IntersectRecord>;
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>>;
+
+    // return a box bounding the object over the time interval [`t0`, `t1`],
+    // or `None` if the object is unbounded
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb>;
+
+    fn transmittance(&self, r: &Ray, t_min: f32, t_max: f32, sampler: &mut dyn Sampler) -> f32 {
+        // estimate of the fraction of light passing through the object along
+        // `r` between `t_min` and `t_max`; by default the object blocks any
+        // light it intersects
+        match self.intersect(r, t_min, t_max, sampler) {
+            Some(_) => 0.0,
+            None => 1.0,
+        }
+    }
 }
 
 pub struct IntersectList {
@@ -40,21 +114,138 @@ impl IntersectList {
     }
 }
 
+impl IntersectList {
+    pub fn finite_bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+        // return a box bounding the bounded objects in `IntersectList`
+        let mut bbox: Option<Aabb> = None;
+        for list_item in self.list.iter() {
+            if let Some(item_bbox) = list_item.bounding_box(t0, t1) {
+                bbox = Some(match bbox {
+                    Some(bbox) => bbox.surrounding(&item_bbox),
+                    None => item_bbox,
+                });
+            }
+        }
+        bbox
+    }
+}
+
 impl IntersectEvent for IntersectList {
-    fn intersect(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<IntersectRecord> {
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
         // return an `IntersectRecord` recording the first intersection of `r`
         // into an intersectable object in `IntersectList`
         let mut t_nearest = t_max;
         let mut intersect: Option<IntersectRecord> = None;
-        for list_item in self.list.iter() {
+        for (index, list_item) in self.list.iter().enumerate() {
             // determine if `r` intersects each object in `IntersectList`
-            if let Some(record) = list_item.intersect(r, t_min, t_nearest) {
+            if let Some(record) = list_item.intersect(r, t_min, t_nearest, sampler) {
                 // record the nearest object intersected
                 t_nearest = record.t;
-                intersect = Some(record);
+                intersect = Some(record.within(index, self.list.len()));
             }
         }
         // return nearest object
         intersect
     }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+        let mut bbox: Option<Aabb> = None;
+        for list_item in self.list.iter() {
+            let item_bbox = list_item.bounding_box(t0, t1)?;
+            bbox = Some(match bbox {
+                Some(bbox) => bbox.surrounding(&item_bbox),
+                None => item_bbox,
+            });
+        }
+        bbox
+    }
+
+    fn transmittance(&self, r: &Ray, t_min: f32, t_max: f32, sampler: &mut dyn Sampler) -> f32 {
+        // product of the transmittances of the objects in `IntersectList`
+        let mut transmittance = 1.0;
+        for list_item in self.list.iter() {
+            transmittance *= list_item.transmittance(r, t_min, t_max, sampler);
+            if transmittance <= 0.0 {
+                return 0.0;
+            }
+        }
+        transmittance
+    }
+}
+
+pub struct FlipNormals<T: IntersectEvent> {
+    pub object: T,
+}
+
+impl<T: IntersectEvent> FlipNormals<T> {
+    pub fn new(object: T) -> FlipNormals<T> {
+        // reverse the surface normals of `object`
+        FlipNormals { object }
+    }
+}
+
+impl<T: IntersectEvent> IntersectEvent for FlipNormals<T> {
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
+        self.object
+            .intersect(r, t_min, t_max, sampler)
+            .map(|mut record| {
+                record.normal = -record.normal;
+                record
+            })
+    }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+        self.object.bounding_box(t0, t1)
+    }
+}
+
+pub struct AlphaMask<T: IntersectEvent, M: Texture> {
+    pub object: T,
+    pub opacity: M,
+}
+
+impl<T: IntersectEvent, M: Texture> AlphaMask<T, M> {
+    pub fn new(object: T, opacity: M) -> AlphaMask<T, M> {
+        // cut `object` by the texture `opacity`; rays pass through the
+        // transparent regions of the object, where the opacity is zero
+        AlphaMask { object, opacity }
+    }
+}
+
+impl<T: IntersectEvent, M: Texture> IntersectEvent for AlphaMask<T, M> {
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
+        // an intersection is accepted with probability given by the opacity
+        // at the point of intersection; otherwise the search continues past it
+        let mut t_min = t_min;
+        loop {
+            let record = self.object.intersect(r, t_min, t_max, sampler)?;
+            let opacity = self.opacity.value(record.u, record.v, record.p);
+            if opacity >= 1.0 || (opacity > 0.0 && sampler.get_1d() < opacity) {
+                return Some(record);
+            }
+            t_min = record.t;
+        }
+    }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+        self.object.bounding_box(t0, t1)
+    }
 }
@@ -1,82 +1,308 @@
+mod aabb;
+mod bdpt;
+mod bvh;
+mod instance;
 mod intersect;
+mod light;
+mod lightbvh;
 mod material;
+mod medium;
+mod microfacet;
+mod noise;
+mod plane;
 mod ray;
+mod rect;
+mod sky;
+mod spectrum;
 mod sphere;
+mod texture;
+mod thinfilm;
 mod vec;
 
 pub mod camera;
+pub mod film;
+pub mod sampler;
+pub mod sampling;
 pub mod scenes;
 
 use std::f32;
+use std::str::FromStr;
 
 use rand::Rng;
 
+use bdpt::Emitters;
 use camera::Camera;
+use film::Film;
 use intersect::{IntersectEvent, IntersectList};
+use light::LightList;
+use material::InteriorStack;
 use ray::Ray;
+use sampler::Sampler;
 use scenes::Params;
-use vec::Vec3;
-
-fn color(r: &Ray, world: &IntersectList, depth: u16, rng: &mut rand_pcg::Pcg64) -> Vec3 {
-    // recursively trace the path of `r` as it intersects objects in `IntersectList`
-    if let Some(record) = world.intersect(r, 0.001, f32::MAX) {
-        if depth < 50 {
-            if let Some((scattered, attenuation)) = record.material.scatter(&r, &record, rng) {
-                return color(&scattered, &world, depth + 1, rng) * attenuation;
+use vec::{Onb, Vec3};
+
+// maximum number of scattering events of a random walk through a scattering
+// interior (Chiang et al., "Practical and Controllable Subsurface Scattering
+// for Production Path Tracing")
+const MAX_WALK_STEPS: u32 = 256;
+
+fn color(
+    r: &Ray,
+    world: &IntersectList,
+    lights: &LightList,
+    params: &Params,
+    sampler: &mut dyn Sampler,
+) -> Vec3 {
+    // trace the path of `r` as it intersects objects in `IntersectList`,
+    // carrying the product of the attenuations along the path as `throughput`
+    // and accumulating light emitted by the surfaces it hits, and light from
+    // the lights in `LightList` reaching them, into `radiance`;
+    // `interiors` holds the nested dielectrics and subsurface media the path
+    // is inside of
+    //
+    // in spectral mode the path carries the values of the spectrum at the
+    // wavelengths rotated from the hero wavelength of `r` (see `spectrum`);
+    // once the path is dispersed only the hero wavelength is carried
+    let mut ray = *r;
+    let mut throughput = Vec3::new(1.0, 1.0, 1.0);
+    let mut radiance = Vec3::new(0.0, 0.0, 0.0);
+    let mut interiors = InteriorStack::new();
+    let mut dispersed = false;
+    let mut lights_visible = true;
+    for depth in 0..=params.max_depth {
+        // free flight through the interior the path is inside of; in a
+        // scattering interior the path travels a distance sampled from the
+        // transmittance of a channel picked in proportion to its throughput,
+        // and scatters inside the interior if it falls short of the next
+        // surface (random walk subsurface scattering), weighted by the
+        // density of the distance averaged over the channels; otherwise the
+        // light is attenuated by beer-lambert absorption along the segment
+        //
+        // the steps of the walk do not count towards `max_depth`, but the
+        // walk is terminated after `MAX_WALK_STEPS` steps
+        let mut steps = 0;
+        let hit = loop {
+            let hit = world.intersect(&ray, 0.001, f32::MAX, sampler);
+            let length = ray.direction.length();
+            let distance = hit.as_ref().map_or(f32::MAX, |record| record.t * length);
+            let absorption = spectrum::color(interiors.absorption(), &ray);
+            let (scattering, anisotropy) = interiors.scattering();
+            let scattering = spectrum::color(scattering, &ray);
+            if scattering.x.max(scattering.y).max(scattering.z) > 0.0 {
+                let extinction = absorption + scattering;
+                let total = throughput.x + throughput.y + throughput.z;
+                if total <= 0.0 || steps == MAX_WALK_STEPS {
+                    return radiance;
+                }
+                let probabilities = throughput / total;
+                let u = sampler.get_1d();
+                let sigma = if u < probabilities.x {
+                    extinction.x
+                } else if u < probabilities.x + probabilities.y {
+                    extinction.y
+                } else {
+                    extinction.z
+                };
+                let t = -(1.0 - sampler.get_1d()).ln() / sigma;
+                if t < distance {
+                    let transmittance = (extinction * -t).exp();
+                    throughput *=
+                        transmittance * scattering / probabilities.dot(extinction * transmittance);
+                    let (local, _) = sampling::henyey_greenstein(sampler.get_2d(), anisotropy);
+                    let direction = ray.direction / length;
+                    ray = Ray {
+                        origin: ray.origin + direction * t,
+                        direction: Onb::new(direction).to_world(local),
+                        ..ray
+                    };
+                    steps += 1;
+                    continue;
+                }
+                let transmittance = (extinction * -distance).exp();
+                throughput *= transmittance / probabilities.dot(transmittance);
+            } else if hit.is_some() {
+                throughput *= (absorption * -distance).exp();
+            }
+            break hit;
+        };
+
+        let mut record = match hit {
+            Some(record) => record,
+            None => {
+                let sky = lights.escaped(ray.direction, lights_visible);
+                return radiance + throughput * spectrum::color(sky, &ray);
+            }
+        };
+
+        // a surface whose interior is overridden by an interior of higher
+        // priority is no interface; the path passes straight through it, into
+        // or out of its interior
+        let interior = record.interior();
+        if let Some(interior) = interior {
+            if !interiors.overrides(interior) {
+                if ray.direction.dot(record.normal) < 0.0 {
+                    interiors.push(interior);
+                } else {
+                    interiors.remove(interior);
+                }
+                ray.origin = record.p;
+                continue;
+            }
+            record.exterior_ior = interiors.exterior_ior(interior);
+        }
+
+        // surfaces of lights in `lights` were sampled by shadow rays; other
+        // emitting surfaces are only found by paths
+        let emitted = record.material.emitted(&ray, &record);
+        if emitted != Vec3::new(0.0, 0.0, 0.0) && (lights_visible || record.light.is_none()) {
+            radiance += throughput * emitted;
+        }
+        if depth == params.max_depth {
+            break;
+        }
+
+        // the secondary wavelengths would scatter in different directions from
+        // a dispersive surface and are dropped; the hero wavelength carries
+        // the estimate for all of them
+        if ray.wavelength.is_some() && !dispersed && record.material.dispersive() {
+            throughput = Vec3::new(3.0 * throughput.x, 0.0, 0.0);
+            dispersed = true;
+        }
+        // connect the path to a light picked from `lights` by a shadow ray,
+        // adding the light arriving through the objects in between
+        if let Some(light) = lights.sample(record.p, sampler) {
+            let bsdf = record.material.eval(&ray, &record, light.wi);
+            if bsdf != Vec3::new(0.0, 0.0, 0.0) {
+                let mut shadow = Ray::new(record.p, light.wi, ray.time);
+                shadow.wavelength = ray.wavelength;
+                // the shadow ray stops short of surfaces of lights
+                let distance = light.distance * (1.0 - 1e-3);
+                let transmittance = world.transmittance(&shadow, 0.001, distance, sampler);
+                radiance +=
+                    throughput * bsdf * spectrum::color(light.radiance, &ray) * transmittance;
+            }
+        }
+
+        match record.material.scatter(&ray, &record, sampler) {
+            Some((scattered, attenuation, delta)) => {
+                // a path transmitted through a surface enters the interior on
+                // the far side of the normal or leaves it for the exterior
+                if let Some(interior) = interior {
+                    let cos_in = ray.direction.dot(record.normal);
+                    let cos_out = scattered.direction.dot(record.normal);
+                    if cos_in * cos_out > 0.0 {
+                        if cos_in < 0.0 {
+                            interiors.push(interior);
+                        } else {
+                            interiors.remove(interior);
+                        }
+                    }
+                }
+                // lights were sampled by a shadow ray for the part of the bsdf
+                // in `eval`, and must not be counted again when a ray sampled
+                // from that part sees them
+                lights_visible = lights.is_empty() || delta;
+                throughput *= attenuation;
+                ray = scattered;
+                ray.wavelength = r.wavelength;
+            }
+            None => break,
+        }
+
+        // russian roulette: terminate dim paths with probability `q` and
+        // reweight surviving paths by 1 / (1 - `q`) to keep the estimate unbiased
+        if depth + 1 >= params.rr_start_depth {
+            let max_throughput = throughput.x.max(throughput.y).max(throughput.z);
+            if max_throughput < 1.0 {
+                let q = 1.0 - max_throughput;
+                if sampler.get_1d() < q {
+                    break;
+                }
+                throughput /= 1.0 - q;
             }
         }
-        return Vec3::new(0.0, 0.0, 0.0);
-    } else {
-        // linear interpolation of blue and white based on y-coordinate
-        let unit_direction = r.direction.unit();
-        let t = 0.5 * (unit_direction.y + 1.0);
-        return Vec3::new(1.0, 1.0, 1.0) * (1.0 - t) + Vec3::new(0.5, 0.7, 1.0) * t;
+    }
+    radiance
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum IntegratorType {
+    Path,
+    Bidirectional,
+}
+
+impl FromStr for IntegratorType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<IntegratorType, String> {
+        match s {
+            "path" => Ok(IntegratorType::Path),
+            "bdpt" => Ok(IntegratorType::Bidirectional),
+            _ => Err(format!("unknown integrator: {}", s)),
+        }
     }
 }
 
 pub fn cast(
     params: &Params,
     world: &IntersectList,
+    lights: &LightList,
     cam: &Camera,
     rng: &mut rand_pcg::Pcg64,
     create_image: bool,
     create_pb: bool,
 ) {
-    // initialize image
-    let mut image = Vec::with_capacity((3 * params.nx * params.ny) as usize);
+    // initialize film; light paths splatted onto it are averaged over the
+    // samples of every pixel
+    let mut film =
+        Film::new(params.nx, params.ny, params.filter).with_splat_scale(1.0 / params.ns as f32);
+
+    // initialize lights sampled by the bidirectional integrator
+    let emitters = Emitters::new(world, lights, cam);
 
     // initialize progress bar
     let pb = indicatif::ProgressBar::new((params.nx * params.ny) as u64);
 
+    // initialize sampler
+    let mut sampler = params.sampler.create(params.ns, rng.gen::<u64>());
+
     for j in (0..params.ny).rev() {
         for i in 0..params.nx {
             if create_pb {
                 pb.inc(1);
             }
-            let mut pixel = Vec3::new(0.0, 0.0, 0.0);
-            for _ in 0..params.ns {
-                let u = (i as f32 + rng.gen::<f32>()) / params.nx as f32;
-                let v = (j as f32 + rng.gen::<f32>()) / params.ny as f32;
-                let r = cam.point(u, v, rng);
-                pixel += color(&r, &world, 0, rng);
-            }
-            pixel /= params.ns as f32;
-            if create_image {
-                image.push((255.99 * pixel.x.sqrt()) as u8);
-                image.push((255.99 * pixel.y.sqrt()) as u8);
-                image.push((255.99 * pixel.z.sqrt()) as u8);
+            for s in 0..params.ns {
+                sampler.start_sample(i, j, s);
+                let (du, dv) = sampler.get_2d();
+                let x = i as f32 + du;
+                let y = j as f32 + dv;
+                let mut r = cam.point(x / params.nx as f32, y / params.ny as f32, sampler.as_mut());
+                if params.spectral {
+                    r.wavelength = Some(spectrum::sample_hero(sampler.get_1d()));
+                }
+                let value = match params.integrator {
+                    IntegratorType::Path => color(&r, world, lights, params, sampler.as_mut()),
+                    IntegratorType::Bidirectional => bdpt::color(
+                        &r,
+                        world,
+                        &emitters,
+                        cam,
+                        params,
+                        sampler.as_mut(),
+                        &mut film,
+                    ),
+                };
+                match r.wavelength {
+                    Some(hero) => {
+                        film.add_spectral_sample(x, y, value, spectrum::wavelengths(hero))
+                    }
+                    None => film.add_sample(x, y, value),
+                }
             }
         }
     }
     if create_image {
-        image::save_buffer(
-            params.output.clone(),
-            &image,
-            params.nx,
-            params.ny,
-            image::RGB(8),
-        )
-        .expect("error saving image");
+        film.save(&params.output);
     }
     if create_pb {
         pb.finish_and_clear();
@@ -0,0 +1,674 @@
+use std::f32;
+
+use crate::aabb::Aabb;
+use crate::intersect::{IntersectEvent, IntersectRecord};
+use crate::lightbvh::{LightBounds, LightBvh};
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::sampling;
+use crate::sky::{Environment, Gradient};
+use crate::vec::{Onb, Vec3};
+
+pub struct LightSample {
+    pub wi: Vec3,
+    pub distance: f32,
+    pub normal: Vec3,
+    pub radiance: Vec3,
+}
+
+pub struct EmissionSample {
+    pub origin: Vec3,
+    pub direction: Vec3,
+    pub normal: Vec3,
+    pub radiance: Vec3,
+    pub pdf_position: f32,
+    pub pdf_direction: f32,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: Vec3, radius: f32) -> BoundingSphere {
+        // sphere bounding the scene; light from lights at infinity enters the
+        // scene through a disk of the same radius facing the light
+        BoundingSphere { center, radius }
+    }
+
+    fn emission(&self, direction: Vec3, u: (f32, f32)) -> Vec3 {
+        // origin of light travelling in `direction` on the disk facing it
+        let (d, _) = sampling::concentric_disk(u);
+        let onb = Onb::new(direction);
+        self.center + (onb.u * d.x + onb.v * d.y - direction) * self.radius
+    }
+
+    pub fn pdf_emission(&self, p: Vec3, direction: Vec3) -> f32 {
+        // density (with respect to area) of the origin on the disk facing
+        // light travelling in `direction`, if such light can reach `p`
+        let offset = p - self.center;
+        let along = offset.dot(direction);
+        if along < -self.radius || offset.dot(offset) - along * along > self.radius * self.radius {
+            return 0.0;
+        }
+        1.0 / (f32::consts::PI * self.radius * self.radius)
+    }
+}
+
+pub trait Light {
+    // sample the light arriving at point `p`, returning the unit direction
+    // `wi` towards the light, the distance to the light and the radiance
+    // arriving from it divided by the density of the sampled direction, or
+    // `None` if no light arrives at `p`
+    fn sample(&self, p: Vec3, u: (f32, f32)) -> Option<LightSample>;
+
+    // sample light leaving the light, returning its origin, direction,
+    // radiance (or intensity, for lights at a point) and the densities of
+    // its origin (with respect to area) and direction (with respect to solid
+    // angle), or `None` if the light does not emit; light from lights at
+    // infinity enters through a disk on the sphere `scene` bounding the scene
+    fn sample_emission(
+        &self,
+        u: (f32, f32),
+        v: (f32, f32),
+        scene: &BoundingSphere,
+    ) -> Option<EmissionSample>;
+
+    // densities with which `sample_emission` samples light leaving `p` in
+    // `direction`; the density of the origin is zero for lights at a point,
+    // and for points not on the light
+    fn pdf_emission(&self, p: Vec3, direction: Vec3, scene: &BoundingSphere) -> (f32, f32);
+
+    fn delta(&self) -> bool {
+        // whether the light is at a single point or emits in a single
+        // direction, so it cannot be hit by paths
+        false
+    }
+
+    fn power(&self, _scene: &BoundingSphere) -> f32 {
+        // estimate of the total power emitted by the light into the scene
+        self.bounds().map_or(0.0, |bounds| bounds.power)
+    }
+
+    fn radiance(&self, _direction: Vec3) -> Vec3 {
+        // radiance of the light seen by rays escaping the scene in
+        // `direction`; most lights cannot be seen
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        // bounds of the light for the light bvh, or `None` for lights at
+        // infinity
+        None
+    }
+}
+
+fn luminance(c: Vec3) -> f32 {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
+
+pub struct PointLight {
+    pub position: Vec3,
+    pub intensity: Vec3,
+}
+
+impl PointLight {
+    pub fn new(position: Vec3, intensity: Vec3) -> PointLight {
+        // construct a light emitting uniformly in all directions from a point
+        //   :position:  position of the light
+        //   :intensity: radiant intensity of the light
+        PointLight {
+            position,
+            intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, p: Vec3, _u: (f32, f32)) -> Option<LightSample> {
+        // light falls off with the inverse square of the distance
+        let offset = self.position - p;
+        let distance = offset.length();
+        if distance <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            wi: offset / distance,
+            distance,
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            radiance: self.intensity / (distance * distance),
+        })
+    }
+
+    fn sample_emission(
+        &self,
+        _u: (f32, f32),
+        v: (f32, f32),
+        _scene: &BoundingSphere,
+    ) -> Option<EmissionSample> {
+        let (direction, pdf_direction) = sampling::uniform_sphere(v);
+        Some(EmissionSample {
+            origin: self.position,
+            direction,
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            radiance: self.intensity,
+            pdf_position: 1.0,
+            pdf_direction,
+        })
+    }
+
+    fn pdf_emission(&self, _p: Vec3, _direction: Vec3, _scene: &BoundingSphere) -> (f32, f32) {
+        (0.0, 0.25 * f32::consts::FRAC_1_PI)
+    }
+
+    fn delta(&self) -> bool {
+        true
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        Some(LightBounds::omnidirectional(
+            Aabb::new(self.position, self.position),
+            4.0 * f32::consts::PI * luminance(self.intensity),
+        ))
+    }
+}
+
+pub struct SpotLight {
+    pub position: Vec3,
+    pub direction: Vec3,
+    pub intensity: Vec3,
+    pub cos_outer: f32,
+    pub cos_inner: f32,
+}
+
+impl SpotLight {
+    pub fn new(
+        position: Vec3,
+        target: Vec3,
+        intensity: Vec3,
+        angle: f32,
+        softness: f32,
+    ) -> SpotLight {
+        // construct a point light emitting inside a cone
+        //   :position:  position of the light
+        //   :target:    point the cone is aimed at
+        //   :intensity: radiant intensity of the light on the axis of the cone
+        //   :angle:     angle of the cone (in degrees)
+        //   :softness:  fraction of the cone over which the intensity falls
+        //               off towards its edge
+        let half_angle = 0.5 * angle.to_radians();
+        SpotLight {
+            position,
+            direction: (target - position).unit(),
+            intensity,
+            cos_outer: half_angle.cos(),
+            cos_inner: (half_angle * (1.0 - softness.clamp(0.0, 1.0))).cos(),
+        }
+    }
+}
+
+impl SpotLight {
+    fn falloff(&self, direction: Vec3) -> f32 {
+        // smooth falloff of the intensity between the inner and outer cones
+        // for light leaving in unit `direction`
+        let cos_theta = direction.dot(self.direction);
+        if cos_theta <= self.cos_outer {
+            0.0
+        } else if cos_theta >= self.cos_inner {
+            1.0
+        } else {
+            let t = (cos_theta - self.cos_outer) / (self.cos_inner - self.cos_outer);
+            t * t * (3.0 - 2.0 * t)
+        }
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, p: Vec3, _u: (f32, f32)) -> Option<LightSample> {
+        // the intensity of a point light is scaled by a smooth falloff
+        // between the inner and outer cones
+        let offset = self.position - p;
+        let distance = offset.length();
+        if distance <= 0.0 {
+            return None;
+        }
+        let wi = offset / distance;
+        let falloff = self.falloff(-wi);
+        if falloff <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            wi,
+            distance,
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            radiance: self.intensity * (falloff / (distance * distance)),
+        })
+    }
+
+    fn sample_emission(
+        &self,
+        _u: (f32, f32),
+        v: (f32, f32),
+        _scene: &BoundingSphere,
+    ) -> Option<EmissionSample> {
+        // directions are sampled uniformly inside the outer cone
+        let (local, pdf_direction) = sampling::uniform_cone(v, self.cos_outer);
+        let direction = Onb::new(self.direction).to_world(local);
+        Some(EmissionSample {
+            origin: self.position,
+            direction,
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            radiance: self.intensity * self.falloff(direction),
+            pdf_position: 1.0,
+            pdf_direction,
+        })
+    }
+
+    fn pdf_emission(&self, _p: Vec3, direction: Vec3, _scene: &BoundingSphere) -> (f32, f32) {
+        if direction.unit().dot(self.direction) <= self.cos_outer {
+            return (0.0, 0.0);
+        }
+        (0.0, 1.0 / (2.0 * f32::consts::PI * (1.0 - self.cos_outer)))
+    }
+
+    fn delta(&self) -> bool {
+        true
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        // light is emitted in the outer cone about `direction`
+        Some(LightBounds::new(
+            Aabb::new(self.position, self.position),
+            2.0 * f32::consts::PI * (1.0 - self.cos_outer) * luminance(self.intensity),
+            self.direction,
+            0.0,
+            self.cos_outer.acos(),
+        ))
+    }
+}
+
+pub struct SphereLight {
+    pub center: Vec3,
+    pub radius: f32,
+    pub radiance: Vec3,
+}
+
+impl SphereLight {
+    pub fn new(center: Vec3, radius: f32, radiance: Vec3) -> SphereLight {
+        // construct a spherical emitter; the sphere is added to the scene
+        // with a `DiffuseLight` material of the same radiance
+        //   :center:   center of the sphere
+        //   :radius:   radius of the sphere
+        //   :radiance: radiance emitted from the surface of the sphere
+        SphereLight {
+            center,
+            radius,
+            radiance,
+        }
+    }
+}
+
+impl Light for SphereLight {
+    fn sample(&self, p: Vec3, u: (f32, f32)) -> Option<LightSample> {
+        // directions are sampled uniformly inside the cone subtended by the
+        // sphere; no light is sampled inside the sphere
+        let offset = self.center - p;
+        let distance2 = offset.dot(offset);
+        let radius2 = self.radius * self.radius;
+        if distance2 <= radius2 {
+            return None;
+        }
+        let cos_max = (1.0 - radius2 / distance2).max(0.0).sqrt();
+        let (local, pdf) = sampling::uniform_cone(u, cos_max);
+        let wi = Onb::new(offset / distance2.sqrt()).to_world(local);
+
+        // distance to the near side of the sphere
+        let b = wi.dot(offset);
+        let t = b - (b * b - distance2 + radius2).max(0.0).sqrt();
+        Some(LightSample {
+            wi,
+            distance: t,
+            normal: (p + wi * t - self.center) / self.radius,
+            radiance: self.radiance / pdf,
+        })
+    }
+
+    fn sample_emission(
+        &self,
+        u: (f32, f32),
+        v: (f32, f32),
+        _scene: &BoundingSphere,
+    ) -> Option<EmissionSample> {
+        // light leaves a point sampled uniformly over the sphere in a
+        // cosine-weighted direction about the normal
+        let (normal, _) = sampling::uniform_sphere(u);
+        let (local, pdf_direction) = sampling::cosine_hemisphere(v);
+        Some(EmissionSample {
+            origin: self.center + normal * self.radius,
+            direction: Onb::new(normal).to_world(local),
+            normal,
+            radiance: self.radiance,
+            pdf_position: 1.0 / (4.0 * f32::consts::PI * self.radius * self.radius),
+            pdf_direction,
+        })
+    }
+
+    fn pdf_emission(&self, p: Vec3, direction: Vec3, _scene: &BoundingSphere) -> (f32, f32) {
+        let offset = p - self.center;
+        if (offset.length() - self.radius).abs() > 1e-3 * self.radius {
+            return (0.0, 0.0);
+        }
+        let cos_theta = (offset / self.radius).dot(direction.unit());
+        (
+            1.0 / (4.0 * f32::consts::PI * self.radius * self.radius),
+            cos_theta.max(0.0) * f32::consts::FRAC_1_PI,
+        )
+    }
+
+    fn bounds(&self) -> Option<LightBounds> {
+        let extent = Vec3::new(self.radius, self.radius, self.radius);
+        Some(LightBounds::omnidirectional(
+            Aabb::new(self.center - extent, self.center + extent),
+            4.0 * f32::consts::PI
+                * f32::consts::PI
+                * self.radius
+                * self.radius
+                * luminance(self.radiance),
+        ))
+    }
+}
+
+pub struct DirectionalLight {
+    pub direction: Vec3,
+    pub irradiance: Vec3,
+    pub cos_max: f32,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vec3, irradiance: Vec3, angular_diameter: f32) -> DirectionalLight {
+        // construct a distant light such as the sun
+        //   :direction:        direction the light travels in
+        //   :irradiance:       irradiance on a surface facing the light
+        //   :angular_diameter: angular diameter of the light (in degrees); 0
+        //                      casts perfectly sharp shadows
+        DirectionalLight {
+            direction: direction.unit(),
+            irradiance,
+            cos_max: (0.5 * angular_diameter.to_radians()).cos(),
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _p: Vec3, u: (f32, f32)) -> Option<LightSample> {
+        // directions are sampled uniformly inside the cone subtended by the
+        // light; the radiance is uniform over the cone so the radiance
+        // divided by the density is the irradiance
+        let wi = if self.cos_max < 1.0 {
+            let (local, _) = sampling::uniform_cone(u, self.cos_max);
+            Onb::new(-self.direction).to_world(local)
+        } else {
+            -self.direction
+        };
+        Some(LightSample {
+            wi,
+            distance: f32::MAX,
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            radiance: self.irradiance,
+        })
+    }
+
+    fn sample_emission(
+        &self,
+        u: (f32, f32),
+        v: (f32, f32),
+        scene: &BoundingSphere,
+    ) -> Option<EmissionSample> {
+        let (direction, radiance, pdf_direction) = if self.cos_max < 1.0 {
+            let (local, pdf) = sampling::uniform_cone(v, self.cos_max);
+            let direction = Onb::new(self.direction).to_world(local);
+            (direction, self.irradiance * pdf, pdf)
+        } else {
+            (self.direction, self.irradiance, 1.0)
+        };
+        Some(EmissionSample {
+            origin: scene.emission(direction, u),
+            direction,
+            normal: direction,
+            radiance,
+            pdf_position: 1.0 / (f32::consts::PI * scene.radius * scene.radius),
+            pdf_direction,
+        })
+    }
+
+    fn pdf_emission(&self, p: Vec3, direction: Vec3, scene: &BoundingSphere) -> (f32, f32) {
+        if self.cos_max >= 1.0 || direction.unit().dot(self.direction) < self.cos_max {
+            return (0.0, 0.0);
+        }
+        (
+            scene.pdf_emission(p, direction.unit()),
+            1.0 / (2.0 * f32::consts::PI * (1.0 - self.cos_max)),
+        )
+    }
+
+    fn delta(&self) -> bool {
+        self.cos_max >= 1.0
+    }
+
+    fn power(&self, scene: &BoundingSphere) -> f32 {
+        f32::consts::PI * scene.radius * scene.radius * luminance(self.irradiance)
+    }
+
+    fn radiance(&self, direction: Vec3) -> Vec3 {
+        // a light of nonzero angular diameter is seen as a disk
+        if self.cos_max >= 1.0 || -direction.unit().dot(self.direction) < self.cos_max {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        self.irradiance / (2.0 * f32::consts::PI * (1.0 - self.cos_max))
+    }
+}
+
+pub struct EnvironmentLight<'a> {
+    pub environment: &'a dyn Environment,
+    pub average: f32,
+}
+
+impl<'a> EnvironmentLight<'a> {
+    pub fn new(environment: &'a dyn Environment) -> EnvironmentLight<'a> {
+        // the environment surrounding the scene as a light at infinity; the
+        // average luminance of the environment is integrated numerically
+        let (n_theta, n_phi) = (16, 32);
+        let d_theta = f32::consts::PI / n_theta as f32;
+        let d_phi = 2.0 * f32::consts::PI / n_phi as f32;
+        let mut total = 0.0;
+        for i in 0..n_theta {
+            let theta = (i as f32 + 0.5) * d_theta;
+            for j in 0..n_phi {
+                let phi = (j as f32 + 0.5) * d_phi;
+                let direction = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    theta.sin() * phi.sin(),
+                );
+                total += luminance(environment.radiance(direction)) * theta.sin() * d_theta * d_phi;
+            }
+        }
+        EnvironmentLight {
+            environment,
+            average: total / (4.0 * f32::consts::PI),
+        }
+    }
+}
+
+impl<'a> Light for EnvironmentLight<'a> {
+    fn sample(&self, _p: Vec3, u: (f32, f32)) -> Option<LightSample> {
+        // directions are sampled uniformly over the sphere
+        let (wi, pdf) = sampling::uniform_sphere(u);
+        Some(LightSample {
+            wi,
+            distance: f32::MAX,
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            radiance: self.environment.radiance(wi) / pdf,
+        })
+    }
+
+    fn sample_emission(
+        &self,
+        u: (f32, f32),
+        v: (f32, f32),
+        scene: &BoundingSphere,
+    ) -> Option<EmissionSample> {
+        let (direction, pdf_direction) = sampling::uniform_sphere(v);
+        Some(EmissionSample {
+            origin: scene.emission(direction, u),
+            direction,
+            normal: direction,
+            radiance: self.environment.radiance(-direction),
+            pdf_position: 1.0 / (f32::consts::PI * scene.radius * scene.radius),
+            pdf_direction,
+        })
+    }
+
+    fn pdf_emission(&self, p: Vec3, direction: Vec3, scene: &BoundingSphere) -> (f32, f32) {
+        (
+            scene.pdf_emission(p, direction.unit()),
+            0.25 * f32::consts::FRAC_1_PI,
+        )
+    }
+
+    fn radiance(&self, direction: Vec3) -> Vec3 {
+        self.environment.radiance(direction)
+    }
+
+    fn power(&self, scene: &BoundingSphere) -> f32 {
+        // the irradiance from a uniform environment of the average luminance
+        // over the disk through which it enters
+        f32::consts::PI * f32::consts::PI * scene.radius * scene.radius * self.average
+    }
+}
+
+pub struct LightSurface<O: IntersectEvent> {
+    pub object: O,
+    pub light: usize,
+}
+
+impl<O: IntersectEvent> LightSurface<O> {
+    pub fn new(object: O, light: usize) -> LightSurface<O> {
+        // emitting `object` whose light is sampled by shadow rays as the
+        // light with index `light` in the `LightList` of the scene
+        LightSurface { object, light }
+    }
+}
+
+impl<O: IntersectEvent> IntersectEvent for LightSurface<O> {
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
+        self.object
+            .intersect(r, t_min, t_max, sampler)
+            .map(|record| IntersectRecord {
+                light: Some(self.light),
+                ..record
+            })
+    }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+        self.object.bounding_box(t0, t1)
+    }
+
+    fn transmittance(&self, r: &Ray, t_min: f32, t_max: f32, sampler: &mut dyn Sampler) -> f32 {
+        self.object.transmittance(r, t_min, t_max, sampler)
+    }
+}
+
+pub struct LightList {
+    pub lights: Vec<Box<dyn Light>>,
+    pub environment: Box<dyn Environment>,
+    bvh: Option<LightBvh>,
+    infinite: Vec<usize>,
+}
+
+impl LightList {
+    pub fn new(lights: Vec<Box<dyn Light>>) -> LightList {
+        // lights of the scene, surrounded by the default gradient sky; the
+        // bounded lights are organized in a light bvh
+        let mut bounded = Vec::new();
+        let mut infinite = Vec::new();
+        for (i, light) in lights.iter().enumerate() {
+            match light.bounds() {
+                Some(bounds) if bounds.power > 0.0 => bounded.push((i, bounds)),
+                Some(_) => {}
+                None => infinite.push(i),
+            }
+        }
+        let bvh = if bounded.is_empty() {
+            None
+        } else {
+            Some(LightBvh::new(bounded))
+        };
+        LightList {
+            lights,
+            environment: Box::new(Gradient),
+            bvh,
+            infinite,
+        }
+    }
+
+    pub fn with_environment<E: Environment + 'static>(mut self, environment: E) -> LightList {
+        // surround the scene by `environment`
+        self.environment = Box::new(environment);
+        self
+    }
+
+    pub fn escaped(&self, direction: Vec3, lights_visible: bool) -> Vec3 {
+        // radiance arriving along rays escaping the scene in `direction` from
+        // the environment and, if `lights_visible`, the lights seen in that
+        // direction; lights are not visible to paths which sampled them by a
+        // shadow ray at their last vertex
+        let mut radiance = self.environment.radiance(direction);
+        if lights_visible {
+            for light in self.lights.iter() {
+                radiance += light.radiance(direction);
+            }
+        }
+        radiance
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    pub fn sample(&self, p: Vec3, sampler: &mut dyn Sampler) -> Option<LightSample> {
+        // sample the light arriving at `p` from a single light, divided by the
+        // probability of picking it; the light bvh is picked as often as each
+        // light at infinity, and picks a light in proportion to its estimated
+        // contribution at `p`; lights emitting no power are left out of the
+        // bvh, so there may be no light to pick from
+        let count = self.infinite.len();
+        if count == 0 && self.bvh.is_none() {
+            return None;
+        }
+        let infinite_prob = match self.bvh {
+            Some(_) => count as f32 / (count as f32 + 1.0),
+            None => 1.0,
+        };
+        let u = sampler.get_1d();
+        let (index, probability) = if u < infinite_prob {
+            let i = ((u / infinite_prob * count as f32) as usize).min(count - 1);
+            (self.infinite[i], infinite_prob / count as f32)
+        } else {
+            let u = (u - infinite_prob) / (1.0 - infinite_prob);
+            let (index, probability) = self.bvh.as_ref()?.sample(p, u)?;
+            (index, (1.0 - infinite_prob) * probability)
+        };
+        let mut sample = self.lights[index].sample(p, sampler.get_2d())?;
+        sample.radiance /= probability;
+        Some(sample)
+    }
+}
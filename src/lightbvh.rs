@@ -0,0 +1,210 @@
+use std::cmp::Ordering;
+use std::f32;
+
+use crate::aabb::Aabb;
+use crate::vec::Vec3;
+
+#[derive(Debug, Copy, Clone)]
+pub struct LightBounds {
+    pub bbox: Aabb,
+    pub power: f32,
+    pub axis: Vec3,
+    pub theta_o: f32,
+    pub theta_e: f32,
+}
+
+impl LightBounds {
+    pub fn new(bbox: Aabb, power: f32, axis: Vec3, theta_o: f32, theta_e: f32) -> LightBounds {
+        // bounds of the light emitted by one or more lights
+        //   :bbox:    box bounding the emitters
+        //   :power:   total emitted power
+        //   :axis:    axis of the cone bounding the emitting directions
+        //   :theta_o: angle of the cone about `axis` bounding the normals (or
+        //             principal directions) of the emitters
+        //   :theta_e: angle beyond `theta_o` over which light is emitted
+        LightBounds {
+            bbox,
+            power,
+            axis: axis.unit(),
+            theta_o,
+            theta_e,
+        }
+    }
+
+    pub fn omnidirectional(bbox: Aabb, power: f32) -> LightBounds {
+        // bounds of emitters emitting light in all directions
+        LightBounds::new(
+            bbox,
+            power,
+            Vec3::new(0.0, 0.0, 1.0),
+            f32::consts::PI,
+            f32::consts::FRAC_PI_2,
+        )
+    }
+
+    fn union(&self, b: &LightBounds) -> LightBounds {
+        // smallest bounds containing both `self` and `b`; the cone about the
+        // merged axis is grown to cover both cones (Conty Estevez and Kulla,
+        // "Importance Sampling of Many Lights with Adaptive Tree Splitting")
+        let (axis, theta_o) = merge_cones(self.axis, self.theta_o, b.axis, b.theta_o);
+        LightBounds {
+            bbox: self.bbox.surrounding(&b.bbox),
+            power: self.power + b.power,
+            axis,
+            theta_o,
+            theta_e: self.theta_e.max(b.theta_e),
+        }
+    }
+
+    fn importance(&self, p: Vec3) -> f32 {
+        // conservative estimate of the light arriving at `p`: the power falls
+        // off with the squared distance to the bounds and is scaled by the
+        // cosine of the smallest angle between the direction to `p` and the
+        // emitting directions; inside the bounds the distance is clamped to
+        // their radius, keeping the importance continuous across them
+        let center = self.bbox.centroid();
+        let radius = 0.5 * (self.bbox.max - self.bbox.min).length();
+        let offset = p - center;
+        let distance2 = offset.dot(offset);
+        if distance2 <= radius * radius {
+            return self.power / (radius * radius).max(1e-8);
+        }
+        let distance = distance2.sqrt();
+        let theta_w = self.axis.dot(offset / distance).clamp(-1.0, 1.0).acos();
+        let theta_b = (radius / distance).asin();
+        let theta = (theta_w - self.theta_o - theta_b).max(0.0);
+        if theta >= self.theta_e {
+            return 0.0;
+        }
+        self.power * theta.cos() / distance2
+    }
+}
+
+fn merge_cones(a: Vec3, theta_a: f32, b: Vec3, theta_b: f32) -> (Vec3, f32) {
+    // axis and angle of a cone containing cones about `a` and `b`
+    let full = (a, f32::consts::PI);
+    if theta_a >= f32::consts::PI || theta_b >= f32::consts::PI {
+        return full;
+    }
+    let theta_d = a.dot(b).clamp(-1.0, 1.0).acos();
+    if (theta_d + theta_b).min(f32::consts::PI) <= theta_a {
+        return (a, theta_a);
+    }
+    if (theta_d + theta_a).min(f32::consts::PI) <= theta_b {
+        return (b, theta_b);
+    }
+    let theta_o = 0.5 * (theta_a + theta_d + theta_b);
+    if theta_o >= f32::consts::PI {
+        return full;
+    }
+
+    // rotate `a` towards `b` about their common normal
+    let normal = a.cross(b);
+    if normal.dot(normal) <= 0.0 {
+        return full;
+    }
+    let normal = normal.unit();
+    let theta_r = theta_o - theta_a;
+    let axis = a * theta_r.cos() + normal.cross(a) * theta_r.sin();
+    (axis, theta_o)
+}
+
+enum LightBvhNode {
+    Leaf(usize),
+    Branch(Box<LightBvh>, Box<LightBvh>),
+}
+
+pub struct LightBvh {
+    bounds: LightBounds,
+    node: LightBvhNode,
+}
+
+impl LightBvh {
+    pub fn new(lights: Vec<(usize, LightBounds)>) -> LightBvh {
+        // construct a bounding volume hierarchy over the bounds of the lights
+        // with indices `lights`
+        LightBvh::build(lights)
+    }
+
+    fn build(mut lights: Vec<(usize, LightBounds)>) -> LightBvh {
+        // split along the longest axis of the centroid bounds at the median
+        assert!(!lights.is_empty(), "light bvh requires at least one light");
+        if lights.len() == 1 {
+            let (index, bounds) = lights.pop().unwrap();
+            return LightBvh {
+                bounds,
+                node: LightBvhNode::Leaf(index),
+            };
+        }
+        let centroids = lights
+            .iter()
+            .map(|(_, bounds)| {
+                let c = bounds.bbox.centroid();
+                Aabb::new(c, c)
+            })
+            .fold(None, |acc: Option<Aabb>, b| match acc {
+                Some(a) => Some(a.surrounding(&b)),
+                None => Some(b),
+            })
+            .unwrap();
+        let extent = centroids.max - centroids.min;
+        let axis = if extent.x > extent.y && extent.x > extent.z {
+            0
+        } else if extent.y > extent.z {
+            1
+        } else {
+            2
+        };
+        let key = |bounds: &LightBounds| {
+            let c = bounds.bbox.centroid();
+            [c.x, c.y, c.z][axis]
+        };
+        lights.sort_by(|a, b| key(&a.1).partial_cmp(&key(&b.1)).unwrap_or(Ordering::Equal));
+        let right_lights = lights.split_off(lights.len() / 2);
+        let left = LightBvh::build(lights);
+        let right = LightBvh::build(right_lights);
+        LightBvh {
+            bounds: left.bounds.union(&right.bounds),
+            node: LightBvhNode::Branch(Box::new(left), Box::new(right)),
+        }
+    }
+
+    pub fn sample(&self, p: Vec3, u: f32) -> Option<(usize, f32)> {
+        // descend the hierarchy picking the child of each node in proportion
+        // to its importance at `p`; returns the index of the light reached and
+        // the probability of picking it
+        let mut node = self;
+        let mut u = u;
+        let mut probability = 1.0;
+        loop {
+            match &node.node {
+                LightBvhNode::Leaf(index) => {
+                    if node.bounds.importance(p) <= 0.0 {
+                        return None;
+                    }
+                    return Some((*index, probability));
+                }
+                LightBvhNode::Branch(left, right) => {
+                    let left_importance = left.bounds.importance(p);
+                    let right_importance = right.bounds.importance(p);
+                    let total = left_importance + right_importance;
+                    if total <= 0.0 {
+                        return None;
+                    }
+                    let left_prob = left_importance / total;
+
+                    // the sample is rescaled to [0, 1) within the child picked
+                    if u < left_prob {
+                        u = (u / left_prob).min(1.0 - f32::EPSILON);
+                        probability *= left_prob;
+                        node = left;
+                    } else {
+                        u = ((u - left_prob) / (1.0 - left_prob)).min(1.0 - f32::EPSILON);
+                        probability *= 1.0 - left_prob;
+                        node = right;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -6,6 +6,7 @@ use getopts::Options;
 use rand::SeedableRng;
 
 use rtwasm::cast;
+use rtwasm::film::{Filter, FilterType};
 use rtwasm::scenes;
 
 fn parse_args() -> Option<scenes::Params> {
@@ -19,6 +20,62 @@ fn parse_args() -> Option<scenes::Params> {
     opts.optopt("s", "samples", "samples per pixel", "INT");
     opts.optopt("r", "random", "random seed for RNG", "INT");
     opts.optopt("o", "output", "output filename", "FILE");
+    opts.optopt(
+        "",
+        "scene",
+        "scene to render (custom, rtiow, cornell, motion, volume, cloud, principled, diffuse, coated, mix, subsurface, lights, sky, emissive, glass, dispersion, thinfilm)",
+        "NAME",
+    );
+    opts.optopt("", "grid", "density grid file for the cloud scene", "FILE");
+    opts.optopt(
+        "",
+        "sampler",
+        "sample generator (independent, stratified, halton, sobol)",
+        "NAME",
+    );
+    opts.optopt(
+        "",
+        "integrator",
+        "light transport integrator (path, bdpt)",
+        "NAME",
+    );
+    opts.optopt(
+        "",
+        "filter",
+        "reconstruction filter (box, tent, gaussian, mitchell, lanczos)",
+        "NAME",
+    );
+    opts.optopt("", "filter-radius", "reconstruction filter radius", "FLOAT");
+    opts.optopt(
+        "",
+        "sun-elevation",
+        "elevation of the sun in the sky scene (in degrees)",
+        "FLOAT",
+    );
+    opts.optopt(
+        "",
+        "sun-azimuth",
+        "azimuth of the sun in the sky scene (in degrees)",
+        "FLOAT",
+    );
+    opts.optopt(
+        "",
+        "turbidity",
+        "turbidity of the sky in the sky scene",
+        "FLOAT",
+    );
+    opts.optflag(
+        "",
+        "spectral",
+        "trace sampled wavelengths instead of RGB (enables dispersion)",
+    );
+    opts.optopt("", "max-depth", "maximum number of bounces per path", "INT");
+    opts.optopt(
+        "",
+        "rr-start-depth",
+        "bounce after which russian roulette may terminate paths",
+        "INT",
+    );
 
     let matches = match opts.parse(&args[1..]) {
         Ok(matches) => matches,
@@ -59,7 +116,64 @@ fn parse_args() -> Option<scenes::Params> {
         output = matches.opt_str("o").unwrap();
     }
 
-    Some(scenes::Params::new(nx, ny, ns, random_seed, output))
+    let mut params = scenes::Params::new(nx, ny, ns, random_seed, output);
+    if matches.opt_present("scene") {
+        params.scene = matches.opt_str("scene").unwrap();
+    }
+    if matches.opt_present("grid") {
+        params.grid = matches.opt_str("grid");
+    }
+    if matches.opt_present("sampler") {
+        params.sampler = match matches.opt_str("sampler").unwrap().parse() {
+            Ok(sampler) => sampler,
+            Err(error) => {
+                println!("{}", error);
+                return None;
+            }
+        };
+    }
+    if matches.opt_present("integrator") {
+        params.integrator = match matches.opt_str("integrator").unwrap().parse() {
+            Ok(integrator) => integrator,
+            Err(error) => {
+                println!("{}", error);
+                return None;
+            }
+        };
+    }
+    if matches.opt_present("filter") {
+        let kind: FilterType = match matches.opt_str("filter").unwrap().parse() {
+            Ok(kind) => kind,
+            Err(error) => {
+                println!("{}", error);
+                return None;
+            }
+        };
+        params.filter = Filter::new(kind, kind.default_radius());
+    }
+    if matches.opt_present("filter-radius") {
+        params.filter.radius = matches.opt_str("filter-radius").unwrap().parse().unwrap();
+    }
+    if matches.opt_present("sun-elevation") {
+        params.sun_elevation = matches.opt_str("sun-elevation").unwrap().parse().unwrap();
+    }
+    if matches.opt_present("sun-azimuth") {
+        params.sun_azimuth = matches.opt_str("sun-azimuth").unwrap().parse().unwrap();
+    }
+    if matches.opt_present("turbidity") {
+        params.turbidity = matches.opt_str("turbidity").unwrap().parse().unwrap();
+    }
+    if matches.opt_present("spectral") {
+        params.spectral = true;
+    }
+    if matches.opt_present("max-depth") {
+        params.max_depth = matches.opt_str("max-depth").unwrap().parse().unwrap();
+    }
+    if matches.opt_present("rr-start-depth") {
+        params.rr_start_depth = matches.opt_str("rr-start-depth").unwrap().parse().unwrap();
+    }
+
+    Some(params)
 }
 
 fn main() {
@@ -73,12 +187,40 @@ fn main() {
     let mut rng = rand_pcg::Pcg64::seed_from_u64(params.random_seed);
 
     // initialize world and camera
-    let (world, cam) = scenes::custom_scene(params.nx, params.ny);
+    let (world, lights, cam) = match params.scene.as_str() {
+        "custom" => scenes::custom_scene(params.nx, params.ny),
+        "rtiow" => scenes::rtiow_scene(params.nx, params.ny, &mut rng),
+        "cornell" => scenes::cornell_scene(params.nx, params.ny),
+        "motion" => scenes::motion_scene(params.nx, params.ny, &mut rng),
+        "volume" => scenes::volume_scene(params.nx, params.ny),
+        "cloud" => scenes::cloud_scene(params.nx, params.ny, params.grid.as_deref(), &mut rng),
+        "principled" => scenes::principled_scene(params.nx, params.ny),
+        "diffuse" => scenes::diffuse_scene(params.nx, params.ny),
+        "coated" => scenes::coated_scene(params.nx, params.ny),
+        "mix" => scenes::mix_scene(params.nx, params.ny, &mut rng),
+        "subsurface" => scenes::subsurface_scene(params.nx, params.ny),
+        "lights" => scenes::lights_scene(params.nx, params.ny),
+        "sky" => scenes::sky_scene(
+            params.nx,
+            params.ny,
+            params.sun_elevation,
+            params.sun_azimuth,
+            params.turbidity,
+        ),
+        "emissive" => scenes::emissive_scene(params.nx, params.ny, &mut rng),
+        "glass" => scenes::glass_scene(params.nx, params.ny),
+        "dispersion" => scenes::dispersion_scene(params.nx, params.ny),
+        "thinfilm" => scenes::thinfilm_scene(params.nx, params.ny),
+        scene => {
+            println!("unknown scene: {}", scene);
+            return;
+        }
+    };
 
     // initialize timer
     let start = time::Instant::now();
 
-    cast(&params, &world, &cam, &mut rng, true, true);
+    cast(&params, &world, &lights, &cam, &mut rng, true, true);
 
     // print elapsed time
     let end = time::Instant::now();
@@ -1,22 +1,14 @@
-use rand::Rng;
-use rand_pcg;
+use std::f32;
 
 use crate::intersect::IntersectRecord;
+use crate::microfacet::TrowbridgeReitz;
 use crate::ray::Ray;
-use crate::vec::Vec3;
-
-pub fn random_point_in_sphere(rng: &mut rand_pcg::Pcg64) -> Vec3 {
-    // return a random point contained inside the unit sphere
-    let unit = Vec3::new(1.0, 1.0, 1.0);
-    loop {
-        // select random points inside the unit cube until a selected point
-        // is contained inside the unit sphere
-        let p = Vec3::new(rng.gen::<f32>(), rng.gen::<f32>(), rng.gen::<f32>()) * 2.0 - unit;
-        if p.squared_length() < 1.0 {
-            return p;
-        }
-    }
-}
+use crate::sampler::{IndependentSampler, Sampler};
+use crate::sampling;
+use crate::spectrum;
+use crate::texture::Texture;
+use crate::thinfilm::ThinFilm;
+use crate::vec::{Onb, Vec3};
 
 pub fn reflect(v: Vec3, n: Vec3) -> Vec3 {
     // reflect ray direction `v` across normal `n`
@@ -37,19 +29,193 @@ pub fn refract(v: Vec3, n: Vec3, ni_over_nt: f32) -> Option<Vec3> {
     }
 }
 
-pub fn schlick(cosine: f32, refractive_idx: f32) -> f32 {
-    // schlick's approximation for the fresnel equations
-    let r0 = ((1.0 - refractive_idx) / (1.0 + refractive_idx)).powi(2);
-    r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
+pub fn fresnel_dielectric(cos_i: f32, eta: f32) -> f32 {
+    // unpolarized fresnel reflectance of a dielectric interface for light
+    // arriving at cosine `cos_i` from the side of the normal; `eta` is the
+    // ratio of refractive indices across the interface (transmitted over
+    // incident), and total internal reflection returns 1
+    let cos_i = cos_i.clamp(0.0, 1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    0.5 * (r_parallel * r_parallel + r_perpendicular * r_perpendicular)
+}
+
+fn fresnel_complex(cos_i: f32, eta: f32, k: f32) -> f32 {
+    // unpolarized fresnel reflectance of a conductor with complex refractive
+    // index eta + ik relative to the outside medium
+    let cos2 = cos_i * cos_i;
+    let sin2 = 1.0 - cos2;
+    let t0 = eta * eta - k * k - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+    let t1 = a2_plus_b2 + cos2;
+    let t2 = 2.0 * cos_i * a;
+    let rs = (t1 - t2) / (t1 + t2);
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+    0.5 * (rs + rp)
+}
+
+pub fn fresnel_conductor(cos_i: f32, eta: Vec3, k: Vec3) -> Vec3 {
+    // fresnel reflectance of a conductor for each color channel
+    let cos_i = cos_i.clamp(0.0, 1.0);
+    Vec3::new(
+        fresnel_complex(cos_i, eta.x, k.x),
+        fresnel_complex(cos_i, eta.y, k.y),
+        fresnel_complex(cos_i, eta.z, k.z),
+    )
+}
+
+fn fresnel_schlick(f0: Vec3, cos_i: f32) -> Vec3 {
+    // schlick's approximation of the fresnel reflectance tinted by `f0`
+    let white = Vec3::new(1.0, 1.0, 1.0);
+    f0 + (white - f0) * (1.0 - cos_i.clamp(0.0, 1.0)).powi(5)
 }
 
 pub trait Material {
+    // scatter `r` from the surface; returns the scattered ray, its attenuation
+    // and whether its direction was sampled from a part of the bsdf which
+    // `eval` leaves out, such as a discrete direction, so that lights the ray
+    // reaches were not already sampled by a shadow ray
     fn scatter(
         &self,
         r: &Ray,
         record: &IntersectRecord,
-        rng: &mut rand_pcg::Pcg64,
-    ) -> Option<(Ray, Vec3)>;
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vec3, bool)>;
+
+    fn eval(&self, _r: &Ray, _record: &IntersectRecord, _wi: Vec3) -> Vec3 {
+        // value of the bsdf times |cos(theta_i)| for light arriving from
+        // direction `wi` and scattered towards the origin of `r`; zero for
+        // materials which only scatter in discrete directions
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    fn pdf(&self, _r: &Ray, _record: &IntersectRecord, _wi: Vec3) -> f32 {
+        // density (with respect to solid angle) with which `scatter` samples
+        // direction `wi`
+        0.0
+    }
+
+    fn emitted(&self, _r: &Ray, _record: &IntersectRecord) -> Vec3 {
+        // radiance emitted from the surface towards the origin of `r`; most
+        // materials do not emit
+        Vec3::new(0.0, 0.0, 0.0)
+    }
+
+    fn volumetric(&self) -> bool {
+        // whether the material is a phase function scattering light inside a
+        // medium rather than at a surface
+        false
+    }
+
+    fn interior(&self) -> Option<Interior> {
+        // medium inside the surface, if paths can be transmitted into it
+        None
+    }
+
+    fn dispersive(&self) -> bool {
+        // whether the direction of scattered rays depends on the wavelength
+        false
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Interior {
+    // identity of the object bounding the interior, set by
+    // `IntersectRecord::interior`; objects with equal interiors are distinct
+    pub id: usize,
+    pub ior: f32,
+    pub priority: u32,
+    pub absorption: Vec3,
+    pub scattering: Vec3,
+    pub anisotropy: f32,
+}
+
+#[derive(Clone)]
+pub struct InteriorStack {
+    entries: Vec<Interior>,
+}
+
+impl InteriorStack {
+    pub fn new() -> InteriorStack {
+        // interiors a path is inside of; the path starts in vacuum
+        InteriorStack {
+            entries: Vec::new(),
+        }
+    }
+
+    fn position(&self, interior: Interior) -> Option<usize> {
+        self.entries
+            .iter()
+            .rposition(|entry| entry.id == interior.id)
+    }
+
+    fn dominant(&self, skip: Option<usize>) -> Option<Interior> {
+        // interior of highest priority, ignoring entry `skip`; of equal
+        // priorities the most recently entered interior wins
+        let mut dominant: Option<Interior> = None;
+        for (i, entry) in self.entries.iter().enumerate() {
+            if Some(i) == skip {
+                continue;
+            }
+            match dominant {
+                Some(d) if d.priority > entry.priority => {}
+                _ => dominant = Some(*entry),
+            }
+        }
+        dominant
+    }
+
+    pub fn absorption(&self) -> Vec3 {
+        // absorption coefficient of the medium the path is in
+        match self.dominant(None) {
+            Some(interior) => interior.absorption,
+            None => Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn scattering(&self) -> (Vec3, f32) {
+        // scattering coefficient and mean cosine of the henyey-greenstein
+        // phase function of the medium the path is in
+        match self.dominant(None) {
+            Some(interior) => (interior.scattering, interior.anisotropy),
+            None => (Vec3::new(0.0, 0.0, 0.0), 0.0),
+        }
+    }
+
+    pub fn overrides(&self, interior: Interior) -> bool {
+        // whether the surface of `interior` is an interface between media,
+        // rather than being overridden by an interior of higher priority
+        match self.dominant(self.position(interior)) {
+            Some(dominant) => interior.priority >= dominant.priority,
+            None => true,
+        }
+    }
+
+    pub fn exterior_ior(&self, interior: Interior) -> f32 {
+        // refractive index of the medium surrounding `interior`
+        match self.dominant(self.position(interior)) {
+            Some(dominant) => dominant.ior,
+            None => 1.0,
+        }
+    }
+
+    pub fn push(&mut self, interior: Interior) {
+        self.entries.push(interior);
+    }
+
+    pub fn remove(&mut self, interior: Interior) {
+        if let Some(i) = self.position(interior) {
+            self.entries.remove(i);
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -66,64 +232,461 @@ impl Lambertian {
 impl Material for Lambertian {
     fn scatter(
         &self,
-        _r: &Ray,
+        r: &Ray,
         record: &IntersectRecord,
-        rng: &mut rand_pcg::Pcg64,
-    ) -> Option<(Ray, Vec3)> {
-        // scattering on a lambertian surface
-        let target = record.p + record.normal + random_point_in_sphere(rng);
-        let scattered = Ray::new(record.p, target - record.p);
-        let attenuation = self.albedo;
-        Some((scattered, attenuation))
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vec3, bool)> {
+        // scattering on a lambertian surface; cosine-weighted sampling of the
+        // hemisphere about the normal on the side of `r` cancels the cosine
+        // term of the bsdf
+        let normal = if r.direction.dot(record.normal) < 0.0 {
+            record.normal
+        } else {
+            -record.normal
+        };
+        let (direction, _) = sampling::cosine_hemisphere(sampler.get_2d());
+        let scattered = Ray::new(record.p, Onb::new(normal).to_world(direction), r.time);
+        let attenuation = spectrum::color(self.albedo, r);
+        Some((scattered, attenuation, false))
+    }
+
+    fn eval(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> Vec3 {
+        if !same_hemisphere(r, record, wi) {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        spectrum::color(self.albedo, r) * (wi.unit().dot(record.normal).abs() / f32::consts::PI)
+    }
+
+    fn pdf(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> f32 {
+        if !same_hemisphere(r, record, wi) {
+            return 0.0;
+        }
+        wi.unit().dot(record.normal).abs() / f32::consts::PI
     }
 }
 
+fn same_hemisphere(r: &Ray, record: &IntersectRecord, wi: Vec3) -> bool {
+    // whether `wi` leaves the surface on the side of the origin of `r`
+    r.direction.dot(record.normal) * wi.dot(record.normal) < 0.0
+}
+
 #[derive(Debug, Copy, Clone)]
-pub struct Metal {
-    pub fuzz: f32,
+pub struct OrenNayar {
     pub albedo: Vec3,
+    pub a: f32,
+    pub b: f32,
 }
 
-impl Metal {
-    pub fn new(fuzz: f32, albedo: Vec3) -> Metal {
-        Metal {
-            fuzz: if fuzz < 1.0 { fuzz } else { 1.0 },
+impl OrenNayar {
+    pub fn new(albedo: Vec3, sigma: f32) -> OrenNayar {
+        // construct a rough diffuse surface of v-shaped lambertian facets
+        //   :albedo: diffuse reflectance
+        //   :sigma:  standard deviation of the facet slope angle (in degrees);
+        //            0 is lambertian
+        let sigma2 = sigma.to_radians().powi(2);
+        OrenNayar {
             albedo,
+            a: 1.0 - sigma2 / (2.0 * (sigma2 + 0.33)),
+            b: 0.45 * sigma2 / (sigma2 + 0.09),
         }
     }
 }
 
-impl Material for Metal {
+impl Material for OrenNayar {
     fn scatter(
         &self,
         r: &Ray,
         record: &IntersectRecord,
-        rng: &mut rand_pcg::Pcg64,
-    ) -> Option<(Ray, Vec3)> {
-        // scattering on a metal surface
-        let reflected = reflect(r.direction.unit(), record.normal);
-        let scattered = Ray::new(
-            record.p,
-            reflected + random_point_in_sphere(rng) * self.fuzz,
-        );
-        let attenuation = self.albedo;
-        if scattered.direction.dot(record.normal) > 0.0 {
-            return Some((scattered, attenuation));
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vec3, bool)> {
+        // scattering on a rough diffuse surface; the hemisphere about the
+        // normal is cosine-weighted sampled, as for a lambertian surface
+        let normal = if r.direction.dot(record.normal) < 0.0 {
+            record.normal
+        } else {
+            -record.normal
+        };
+        let (direction, _) = sampling::cosine_hemisphere(sampler.get_2d());
+        let wi = Onb::new(normal).to_world(direction);
+        let pdf = self.pdf(r, record, wi);
+        if pdf <= 0.0 {
+            return None;
+        }
+        let attenuation = self.eval(r, record, wi) / pdf;
+        Some((Ray::new(record.p, wi, r.time), attenuation, false))
+    }
+
+    fn eval(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> Vec3 {
+        // qualitative oren-nayar model: the lambertian term is scaled by `a`
+        // and light is retroreflected by `b` towards the direction of
+        // incidence (Oren and Nayar, "Generalization of Lambert's Reflectance
+        // Model")
+        if !same_hemisphere(r, record, wi) {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        let frame = Onb::new(record.normal);
+        let wo = frame.to_local(-r.direction.unit());
+        let wi = frame.to_local(wi.unit());
+        let cos_o = wo.z.abs();
+        let cos_i = wi.z.abs();
+        let sin_o = (1.0 - cos_o * cos_o).max(0.0).sqrt();
+        let sin_i = (1.0 - cos_i * cos_i).max(0.0).sqrt();
+
+        // cosine of the azimuthal angle between the directions
+        let cos_phi = if sin_o > 1e-4 && sin_i > 1e-4 {
+            ((wo.x * wi.x + wo.y * wi.y) / (sin_o * sin_i)).max(0.0)
+        } else {
+            0.0
+        };
+
+        // sin(alpha) * tan(beta) for alpha = max(theta_i, theta_o) and
+        // beta = min(theta_i, theta_o)
+        let (sin_alpha, tan_beta) = if cos_i > cos_o {
+            (sin_o, sin_i / cos_i)
+        } else {
+            (sin_i, sin_o / cos_o.max(1e-4))
+        };
+        spectrum::color(self.albedo, r)
+            * ((self.a + self.b * cos_phi * sin_alpha * tan_beta) * cos_i / f32::consts::PI)
+    }
+
+    fn pdf(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> f32 {
+        if !same_hemisphere(r, record, wi) {
+            return 0.0;
+        }
+        wi.unit().dot(record.normal).abs() / f32::consts::PI
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Conductor {
+    pub eta: Vec3,
+    pub k: Vec3,
+    pub distribution: TrowbridgeReitz,
+    pub thin_film: Option<ThinFilm>,
+}
+
+impl Conductor {
+    pub fn new(eta: Vec3, k: Vec3, roughness: f32) -> Conductor {
+        // construct a rough conductor
+        //   :eta:       real part of the complex refractive index (per channel)
+        //   :k:         imaginary part of the complex refractive index (per channel)
+        //   :roughness: surface roughness in [0, 1]; 0 is a perfect mirror
+        Conductor {
+            eta,
+            k,
+            distribution: TrowbridgeReitz::new(roughness, roughness),
+            thin_film: None,
+        }
+    }
+
+    pub fn with_anisotropy(mut self, roughness_x: f32, roughness_y: f32) -> Conductor {
+        // use separate roughness along the tangent (the direction of
+        // increasing surface coordinate `u`) and the bitangent, e.g. for
+        // brushed metals
+        self.distribution = TrowbridgeReitz::new(roughness_x, roughness_y);
+        self
+    }
+
+    pub fn with_thin_film(mut self, thickness: f32, ior: f32) -> Conductor {
+        // coat the conductor with a transparent film of `thickness` (in nm),
+        // e.g. an oxide layer, whose interference produces iridescent color
+        self.thin_film = Some(ThinFilm::new(thickness, ior));
+        self
+    }
+
+    fn fresnel(&self, cos: f32, r: &Ray, record: &IntersectRecord) -> Vec3 {
+        // reflectance of a microfacet at angle acos(`cos`) to the light; the
+        // complex refractive index is relative to the exterior medium, e.g.
+        // for a conductor under the coating of `Coated`
+        let eta = spectrum::color(self.eta, r);
+        let k = spectrum::color(self.k, r);
+        match self.thin_film {
+            Some(film) => film.reflectance(
+                cos,
+                record.exterior_ior,
+                eta,
+                k,
+                spectrum::channel_wavelengths(r),
+            ),
+            None => fresnel_conductor(cos, eta / record.exterior_ior, k / record.exterior_ior),
+        }
+    }
+
+    pub fn from_reflectance(reflectance: Vec3, roughness: f32) -> Conductor {
+        // construct a conductor with reflectance `reflectance` at normal
+        // incidence; with eta = 1 the normal reflectance k^2 / (4 + k^2) is
+        // solved for k
+        let k = |r: f32| {
+            let r = r.clamp(0.0, 0.999);
+            2.0 * (r / (1.0 - r)).sqrt()
+        };
+        Conductor::new(
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(k(reflectance.x), k(reflectance.y), k(reflectance.z)),
+            roughness,
+        )
+    }
+
+    pub fn gold(roughness: f32) -> Conductor {
+        Conductor::new(
+            Vec3::new(0.143, 0.374, 1.442),
+            Vec3::new(3.983, 2.385, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f32) -> Conductor {
+        Conductor::new(
+            Vec3::new(0.155, 0.116, 0.138),
+            Vec3::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f32) -> Conductor {
+        Conductor::new(
+            Vec3::new(0.200, 0.924, 1.102),
+            Vec3::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: f32) -> Conductor {
+        Conductor::new(
+            Vec3::new(1.657, 0.880, 0.521),
+            Vec3::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(
+        &self,
+        r: &Ray,
+        record: &IntersectRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vec3, bool)> {
+        // scattering on a rough conductor; a microfacet normal is sampled from
+        // the visible normals so the weight f * cos / pdf reduces to
+        // F * G2 / G1, and light masked by neighbouring microfacets is absorbed
+        let onb = Onb::from_tangent(record.normal, record.tangent);
+        let wo = onb.to_local(-r.direction.unit());
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let wm = self.distribution.sample_visible(wo, sampler.get_2d());
+        let wi = reflect(-wo, wm);
+        if wi.z <= 0.0 {
+            return None;
+        }
+        let attenuation = self.fresnel(wo.dot(wm), r, record)
+            * (self.distribution.g(wo, wi) / self.distribution.g1(wo));
+        let scattered = Ray::new(record.p, onb.to_world(wi), r.time);
+        Some((scattered, attenuation, self.distribution.is_smooth()))
+    }
+
+    fn eval(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> Vec3 {
+        // D * G2 * F / (4 * cos(theta_o)) after the cosine term cancels;
+        // zero for a smooth conductor
+        let onb = Onb::from_tangent(record.normal, record.tangent);
+        let wo = onb.to_local(-r.direction.unit());
+        let wi = onb.to_local(wi.unit());
+        if self.distribution.is_smooth() || wo.z <= 0.0 || wi.z <= 0.0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        let wm = (wo + wi).unit();
+        self.fresnel(wo.dot(wm), r, record)
+            * (self.distribution.d(wm) * self.distribution.g(wo, wi) / (4.0 * wo.z))
+    }
+
+    fn pdf(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> f32 {
+        let onb = Onb::from_tangent(record.normal, record.tangent);
+        let wo = onb.to_local(-r.direction.unit());
+        let wi = onb.to_local(wi.unit());
+        if self.distribution.is_smooth() || wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let wm = (wo + wi).unit();
+        self.distribution.pdf(wo, wm) / (4.0 * wo.dot(wm))
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum Dispersion {
+    Cauchy { a: f32, b: f32 },
+    Sellmeier { b: [f32; 3], c: [f32; 3] },
+}
+
+impl Dispersion {
+    pub fn cauchy(a: f32, b: f32) -> Dispersion {
+        // n = a + b / lambda^2 with `b` in um^2
+        Dispersion::Cauchy { a, b }
+    }
+
+    pub fn sellmeier(b: [f32; 3], c: [f32; 3]) -> Dispersion {
+        // n^2 = 1 + sum(b_i * lambda^2 / (lambda^2 - c_i)) with `c` in um^2
+        Dispersion::Sellmeier { b, c }
+    }
+
+    pub fn bk7() -> Dispersion {
+        // schott N-BK7 crown glass
+        Dispersion::sellmeier(
+            [1.039_612, 0.231_792_3, 1.010_469_5],
+            [0.006_000_7, 0.020_017_914, 103.560_65],
+        )
+    }
+
+    pub fn diamond() -> Dispersion {
+        Dispersion::sellmeier([0.3306, 4.3356, 0.0], [0.030_625, 0.011_236, 0.0])
+    }
+
+    pub fn ior(&self, lambda: f32) -> f32 {
+        // refractive index at wavelength `lambda` (in nm)
+        let l2 = (lambda / 1000.0).powi(2);
+        match *self {
+            Dispersion::Cauchy { a, b } => a + b / l2,
+            Dispersion::Sellmeier { b, c } => {
+                let n2 = 1.0 + (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum::<f32>();
+                n2.sqrt()
+            }
         }
-        None
     }
 }
 
 #[derive(Debug, Copy, Clone)]
 pub struct Dielectric {
     pub ri: f32,
-    pub fuzz: f32,
+    pub distribution: TrowbridgeReitz,
     pub albedo: Vec3,
+    pub absorption: Vec3,
+    pub priority: u32,
+    pub dispersion: Option<Dispersion>,
+    pub thin_film: Option<ThinFilm>,
 }
 
 impl Dielectric {
-    pub fn new(ri: f32, fuzz: f32, albedo: Vec3) -> Dielectric {
-        Dielectric { ri, fuzz, albedo }
+    pub fn new(ri: f32, roughness: f32, albedo: Vec3) -> Dielectric {
+        // construct a rough dielectric
+        //   :ri:        refractive index
+        //   :roughness: surface roughness in [0, 1]; 0 is smooth glass
+        //   :albedo:    tint applied to reflected and transmitted light
+        Dielectric {
+            ri,
+            distribution: TrowbridgeReitz::new(roughness, roughness),
+            albedo,
+            absorption: Vec3::new(0.0, 0.0, 0.0),
+            priority: 0,
+            dispersion: None,
+            thin_film: None,
+        }
+    }
+
+    pub fn with_thin_film(mut self, thickness: f32, ior: f32) -> Dielectric {
+        // coat the dielectric with a transparent film of `thickness` (in nm),
+        // e.g. soap or oil, whose interference produces iridescent color
+        self.thin_film = Some(ThinFilm::new(thickness, ior));
+        self
+    }
+
+    pub fn with_dispersion(mut self, dispersion: Dispersion) -> Dielectric {
+        // vary the refractive index with the wavelength in spectral mode; the
+        // index at 550nm replaces `ri` elsewhere
+        self.ri = dispersion.ior(550.0);
+        self.dispersion = Some(dispersion);
+        self
+    }
+
+    pub fn with_priority(mut self, priority: u32) -> Dielectric {
+        // where the interiors of dielectrics overlap the one with the highest
+        // priority fills the overlap, e.g. a bubble overrides the ice around it
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_absorption(mut self, color: Vec3, density: f32) -> Dielectric {
+        // absorb light travelling through the interior following the
+        // beer-lambert law; `color` is the fraction of light transmitted
+        // through a thickness of 1 / `density`
+        let sigma = |c: f32| -c.clamp(1e-4, 1.0).ln() * density;
+        self.absorption = Vec3::new(sigma(color.x), sigma(color.y), sigma(color.z));
+        self
+    }
+
+    fn shading_frame(&self, r: &Ray, record: &IntersectRecord) -> (Onb, Vec3, f32, f32) {
+        // the shading frame is flipped to the side of the incoming light;
+        // returns the frame, `wo` in the flipped frame, the relative
+        // refractive index across the surface and the sign of the flip
+        let onb = Onb::new(record.normal);
+        let wo = onb.to_local(-r.direction.unit());
+        let ri = self.ior(r);
+        if wo.z > 0.0 {
+            // light passes from the exterior medium to the dielectric
+            (onb, wo, ri / record.exterior_ior, 1.0)
+        } else {
+            // light passes from the dielectric to the exterior medium
+            (onb, -wo, record.exterior_ior / ri, -1.0)
+        }
+    }
+
+    fn ior(&self, r: &Ray) -> f32 {
+        // refractive index at the hero wavelength of `r`
+        match (self.dispersion, r.wavelength) {
+            (Some(dispersion), Some(lambda)) => dispersion.ior(lambda),
+            _ => self.ri,
+        }
+    }
+
+    fn reflectance(
+        &self,
+        cos: f32,
+        eta: f32,
+        sign: f32,
+        r: &Ray,
+        record: &IntersectRecord,
+    ) -> Vec3 {
+        // reflectance of a microfacet at angle acos(`cos`) to the light, for
+        // the relative refractive index `eta` and sign of the flip of the
+        // shading frame returned by `shading_frame`
+        match self.thin_film {
+            Some(film) => {
+                let ri = self.ior(r);
+                let (n1, n3) = if sign > 0.0 {
+                    (record.exterior_ior, ri)
+                } else {
+                    (ri, record.exterior_ior)
+                };
+                film.reflectance(
+                    cos,
+                    n1,
+                    Vec3::new(n3, n3, n3),
+                    Vec3::new(0.0, 0.0, 0.0),
+                    spectrum::channel_wavelengths(r),
+                )
+            }
+            None => {
+                let reflectance = fresnel_dielectric(cos, eta);
+                Vec3::new(reflectance, reflectance, reflectance)
+            }
+        }
+    }
+
+    fn half_vector(wo: Vec3, wi: Vec3, eta: f32) -> Option<Vec3> {
+        // microfacet normal reflecting or refracting `wo` into `wi`
+        if wo.z <= 0.0 || wi.z == 0.0 {
+            return None;
+        }
+        let wm = if wi.z > 0.0 {
+            (wo + wi).unit()
+        } else {
+            (wo + wi * eta).unit()
+        };
+        let wm = if wm.z < 0.0 { -wm } else { wm };
+        if wm.dot(wo) <= 0.0 || wm.dot(wi) * wi.z <= 0.0 {
+            return None;
+        }
+        Some(wm)
     }
 }
 
@@ -132,43 +695,967 @@ impl Material for Dielectric {
         &self,
         r: &Ray,
         record: &IntersectRecord,
-        rng: &mut rand_pcg::Pcg64,
-    ) -> Option<(Ray, Vec3)> {
-        // scattering on a dielectric surface
-        let attenuation = self.albedo;
-        let cos_theta = r.direction.dot(record.normal);
-
-        let (outward_normal, ni_over_nt, cos) = if cos_theta > 0.0 {
-            // light passes from dielectric to vacuum
-            let outward_normal = -record.normal;
-            let ni_over_nt = self.ri;
-            let cos = self.ri * cos_theta / r.direction.length();
-            (outward_normal, ni_over_nt, cos)
-        } else {
-            // light passes from vacuum to dielectric
-            let outward_normal = record.normal;
-            let ni_over_nt = 1.0 / self.ri;
-            let cos = -cos_theta / r.direction.length();
-            (outward_normal, ni_over_nt, cos)
-        };
-
-        // both reflection and refraction occur in a dielectric material; return reflected
-        // or refracted light based on `reflect_prob`
-        if let Some(refracted) = refract(r.direction, outward_normal, ni_over_nt) {
-            let reflect_prob = schlick(cos, self.ri);
-            if rng.gen::<f32>() >= reflect_prob {
-                let scattered = Ray::new(
-                    record.p,
-                    refracted + random_point_in_sphere(rng) * self.fuzz,
-                );
-                return Some((scattered, attenuation));
-            }
-        }
-        let reflected = reflect(r.direction.unit(), record.normal);
-        let scattered = Ray::new(
-            record.p,
-            reflected + random_point_in_sphere(rng) * self.fuzz,
-        );
-        Some((scattered, attenuation))
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vec3, bool)> {
+        // scattering on a rough dielectric surface (Walter et al., "Microfacet
+        // Models for Refraction through Rough Surfaces"); the shading frame is
+        // flipped to the side of the incoming light, a visible microfacet
+        // normal is sampled and the microfacet reflects or refracts with
+        // probability given by its fresnel reflectance, leaving the weight
+        // G2 / G1; the reflectance of a thin film varies between channels and
+        // the average reflectance is used instead, weighting each channel by
+        // its ratio to the average
+        let (onb, wo, eta, sign) = self.shading_frame(r, record);
+        if wo.z == 0.0 {
+            return None;
+        }
+
+        let wm = self.distribution.sample_visible(wo, sampler.get_2d());
+        let reflectance = self.reflectance(wo.dot(wm), eta, sign, r, record);
+        let reflect_prob = (reflectance.x + reflectance.y + reflectance.z) / 3.0;
+        let (wi, weight) = if sampler.get_1d() < reflect_prob {
+            let wi = reflect(-wo, wm);
+            if wi.z <= 0.0 {
+                return None;
+            }
+            (wi, reflectance / reflect_prob)
+        } else {
+            let wi = refract(-wo, wm, 1.0 / eta)?;
+            if wi.z >= 0.0 {
+                return None;
+            }
+            let white = Vec3::new(1.0, 1.0, 1.0);
+            (wi, (white - reflectance) / (1.0 - reflect_prob))
+        };
+
+        let attenuation = spectrum::color(self.albedo, r)
+            * weight
+            * (self.distribution.g(wo, wi) / self.distribution.g1(wo));
+        let scattered = Ray::new(record.p, onb.to_world(wi * sign), r.time);
+        Some((scattered, attenuation, self.distribution.is_smooth()))
+    }
+
+    fn eval(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> Vec3 {
+        // reflection D * G2 * F / (4 * cos(theta_o)) and transmission
+        // D * G2 * (1 - F) * |wi.wm| * |wo.wm| / (cos(theta_o) * denom^2)
+        // after the cosine term cancels; zero for smooth glass
+        let zero = Vec3::new(0.0, 0.0, 0.0);
+        if self.distribution.is_smooth() {
+            return zero;
+        }
+        let (onb, wo, eta, sign) = self.shading_frame(r, record);
+        let wi = onb.to_local(wi.unit()) * sign;
+        let wm = match Dielectric::half_vector(wo, wi, eta) {
+            Some(wm) => wm,
+            None => return zero,
+        };
+        let reflectance = self.reflectance(wo.dot(wm), eta, sign, r, record);
+        let albedo = spectrum::color(self.albedo, r);
+        let dg = self.distribution.d(wm) * self.distribution.g(wo, wi);
+        if wi.z > 0.0 {
+            albedo * reflectance * (dg / (4.0 * wo.z))
+        } else {
+            let denom = (wi.dot(wm) + wo.dot(wm) / eta).powi(2);
+            let white = Vec3::new(1.0, 1.0, 1.0);
+            albedo * (white - reflectance) * (dg * (wi.dot(wm) * wo.dot(wm)).abs() / (wo.z * denom))
+        }
+    }
+
+    fn pdf(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> f32 {
+        if self.distribution.is_smooth() {
+            return 0.0;
+        }
+        let (onb, wo, eta, sign) = self.shading_frame(r, record);
+        let wi = onb.to_local(wi.unit()) * sign;
+        let wm = match Dielectric::half_vector(wo, wi, eta) {
+            Some(wm) => wm,
+            None => return 0.0,
+        };
+        let reflectance = self.reflectance(wo.dot(wm), eta, sign, r, record);
+        let reflect_prob = (reflectance.x + reflectance.y + reflectance.z) / 3.0;
+        let pdf = self.distribution.pdf(wo, wm);
+        if wi.z > 0.0 {
+            reflect_prob * pdf / (4.0 * wo.dot(wm))
+        } else {
+            let denom = (wi.dot(wm) + wo.dot(wm) / eta).powi(2);
+            (1.0 - reflect_prob) * pdf * wi.dot(wm).abs() / denom
+        }
+    }
+
+    fn interior(&self) -> Option<Interior> {
+        Some(Interior {
+            id: 0,
+            ior: self.ri,
+            priority: self.priority,
+            absorption: self.absorption,
+            scattering: Vec3::new(0.0, 0.0, 0.0),
+            anisotropy: 0.0,
+        })
+    }
+
+    fn dispersive(&self) -> bool {
+        self.dispersion.is_some()
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Subsurface {
+    pub boundary: Dielectric,
+    pub albedo: Vec3,
+    pub radius: Vec3,
+    pub anisotropy: f32,
+}
+
+impl Subsurface {
+    pub fn new(albedo: Vec3, radius: Vec3, ior: f32, roughness: f32) -> Subsurface {
+        // construct a translucent material scattering light below its surface
+        //   :albedo:    color of the material, i.e. the fraction of light
+        //               re-emerging after scattering inside it
+        //   :radius:    mean free path inside the material (per channel)
+        //   :ior:       refractive index of the material
+        //   :roughness: surface roughness in [0, 1]; 0 is smooth
+        Subsurface {
+            boundary: Dielectric::new(ior, roughness, Vec3::new(1.0, 1.0, 1.0)),
+            albedo,
+            radius,
+            anisotropy: 0.0,
+        }
+    }
+
+    pub fn with_anisotropy(mut self, g: f32) -> Subsurface {
+        // scatter with a henyey-greenstein phase function of mean cosine `g`;
+        // the scattering coefficient is scaled by 1 / (1 - g) to keep the
+        // reduced scattering coefficient and the appearance of the material
+        self.anisotropy = g;
+        self
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(
+        &self,
+        r: &Ray,
+        record: &IntersectRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vec3, bool)> {
+        // paths are reflected from or refracted through the dielectric
+        // surface; inside, the integrator random walks through the interior
+        self.boundary.scatter(r, record, sampler)
+    }
+
+    fn eval(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> Vec3 {
+        self.boundary.eval(r, record, wi)
+    }
+
+    fn pdf(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> f32 {
+        self.boundary.pdf(r, record, wi)
+    }
+
+    fn interior(&self) -> Option<Interior> {
+        // the single scattering albedo giving the multiple scattering
+        // `albedo` (Chiang et al., "Practical and Controllable Subsurface
+        // Scattering for Production Path Tracing")
+        let single = |a: f32| {
+            let a = a.clamp(0.0, 0.999);
+            let s = 4.09712 + 4.20863 * a - (9.59217 + 41.6808 * a + 17.7126 * a * a).sqrt();
+            1.0 - s * s
+        };
+        // by the similarity relation only scattering is scaled by the
+        // anisotropy, keeping the reduced scattering coefficient while the
+        // absorption is left unchanged
+        let coefficients = |a: f32, radius: f32| {
+            let sigma_t = 1.0 / radius.max(1e-4);
+            (
+                sigma_t * (1.0 - single(a)),
+                sigma_t * single(a) / (1.0 - self.anisotropy),
+            )
+        };
+        let (ax, sx) = coefficients(self.albedo.x, self.radius.x);
+        let (ay, sy) = coefficients(self.albedo.y, self.radius.y);
+        let (az, sz) = coefficients(self.albedo.z, self.radius.z);
+        Some(Interior {
+            absorption: Vec3::new(ax, ay, az),
+            scattering: Vec3::new(sx, sy, sz),
+            anisotropy: self.anisotropy,
+            ..self.boundary.interior()?
+        })
+    }
+}
+
+// number of times a path may bounce between the base and the coating of a
+// coated material before it is terminated
+const COATING_MAX_BOUNCES: u32 = 16;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Coated<M: Material> {
+    pub base: M,
+    pub ior: f32,
+    pub distribution: TrowbridgeReitz,
+    pub absorption: Vec3,
+}
+
+impl<M: Material> Coated<M> {
+    pub fn new(base: M, ior: f32, roughness: f32) -> Coated<M> {
+        // construct a dielectric coating over a base material
+        //   :base:      material under the coating
+        //   :ior:       refractive index of the coating
+        //   :roughness: roughness of the coating surface in [0, 1]; 0 is smooth
+        Coated {
+            base,
+            ior,
+            distribution: TrowbridgeReitz::new(roughness, roughness),
+            absorption: Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn with_absorption(mut self, color: Vec3, thickness: f32) -> Coated<M> {
+        // tint the coating following the beer-lambert law; `color` is the
+        // fraction of light transmitted along the normal through a coating
+        // of unit thickness, scaled to a coating of `thickness`
+        let sigma = |c: f32| -c.clamp(1e-4, 1.0).ln() * thickness;
+        self.absorption = Vec3::new(sigma(color.x), sigma(color.y), sigma(color.z));
+        self
+    }
+
+    fn escape(&self, wb: Vec3, wi: Vec3, eta: f32) -> f32 {
+        // density of light travelling up through the coating in direction
+        // `wb` escaping through the top of a rough coating towards `wi`, times
+        // the weight `scatter` gives it; `wb` is seen from inside the coating
+        // with the shading frame flipped to its side, as in `scatter`
+        let wm = (wb - wi / eta).unit();
+        let wm = if wm.z < 0.0 { -wm } else { wm };
+        if wb.dot(wm) <= 0.0 || wi.dot(wm) <= 0.0 {
+            return 0.0;
+        }
+        let denom = (eta * wb.dot(wm) - wi.dot(wm)).powi(2);
+        (1.0 - fresnel_dielectric(wb.dot(wm), 1.0 / eta))
+            * self.distribution.g(wb, wi)
+            * self.distribution.d(wm)
+            * wb.dot(wm)
+            * wi.dot(wm)
+            / (wb.z * denom)
+    }
+}
+
+impl<M: Material> Material for Coated<M> {
+    fn scatter(
+        &self,
+        r: &Ray,
+        record: &IntersectRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vec3, bool)> {
+        // random walk through a thin coating over the base: at the top of the
+        // coating a visible microfacet normal is sampled and the path is
+        // reflected or refracted with probability given by its fresnel
+        // reflectance; refracted paths are absorbed along the slant through
+        // the coating and scattered by the base until they escape through
+        // the top of the coating, are transmitted through the base or the
+        // maximum number of bounces is reached; the path is sampled from the
+        // part of the bsdf left out of `eval` if it is reflected from a smooth
+        // coating, escapes through it right after a discrete direction of the
+        // base, or is transmitted through the base
+        let normal = if r.direction.dot(record.normal) < 0.0 {
+            record.normal
+        } else {
+            -record.normal
+        };
+        let onb = Onb::new(normal);
+        let wo = onb.to_local(-r.direction.unit());
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let eta = self.ior / record.exterior_ior;
+        let absorption = spectrum::color(self.absorption, r);
+        let transmittance = |cos: f32| (absorption * (-1.0 / cos.abs().max(1e-4))).exp();
+
+        // interface at the top of the coating; the path is reflected from or
+        // enters the coating
+        let wm = self.distribution.sample_visible(wo, sampler.get_2d());
+        if sampler.get_1d() < fresnel_dielectric(wo.dot(wm), eta) {
+            let wi = reflect(-wo, wm);
+            if wi.z <= 0.0 {
+                return None;
+            }
+            let attenuation =
+                Vec3::new(1.0, 1.0, 1.0) * (self.distribution.g(wo, wi) / self.distribution.g1(wo));
+            let scattered = Ray::new(record.p, onb.to_world(wi), r.time);
+            return Some((scattered, attenuation, self.distribution.is_smooth()));
+        }
+        let mut wt = refract(-wo, wm, 1.0 / eta)?;
+        if wt.z >= 0.0 {
+            return None;
+        }
+        let mut weight =
+            Vec3::new(1.0, 1.0, 1.0) * (self.distribution.g(wo, wt) / self.distribution.g1(wo));
+
+        // the base sees the coating as its exterior medium
+        let base_record = IntersectRecord {
+            exterior_ior: self.ior,
+            ..*record
+        };
+        for _ in 0..COATING_MAX_BOUNCES {
+            // travel down through the coating and scatter from the base
+            weight *= transmittance(wt.z);
+            let mut incident = Ray::new(record.p, onb.to_world(wt), r.time);
+            incident.wavelength = r.wavelength;
+            let (scattered, attenuation, delta) =
+                self.base.scatter(&incident, &base_record, sampler)?;
+            weight *= attenuation;
+            let wb = onb.to_local(scattered.direction.unit());
+            if wb.z <= 0.0 {
+                // transmitted through the base
+                return Some((scattered, weight, true));
+            }
+
+            // travel up through the coating to its top, seen from inside the
+            // coating with the shading frame flipped to its side
+            weight *= transmittance(wb.z);
+            let wm = self.distribution.sample_visible(wb, sampler.get_2d());
+            if sampler.get_1d() < fresnel_dielectric(wb.dot(wm), 1.0 / eta) {
+                // reflected back down to the base
+                let wi = reflect(-wb, wm);
+                if wi.z <= 0.0 {
+                    return None;
+                }
+                weight *= self.distribution.g(wb, wi) / self.distribution.g1(wb);
+                wt = -wi;
+            } else {
+                // escapes through the top of the coating
+                let wi = refract(-wb, wm, eta)?;
+                if wi.z >= 0.0 {
+                    return None;
+                }
+                weight *= self.distribution.g(wb, wi) / self.distribution.g1(wb);
+                let scattered = Ray::new(record.p, onb.to_world(-wi), r.time);
+                return Some((scattered, weight, delta && self.distribution.is_smooth()));
+            }
+        }
+        None
+    }
+
+    fn eval(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> Vec3 {
+        // unbiased stochastic estimate of the layered bsdf, leaving out the
+        // directions `scatter` reports as discrete (Guo et al., "Position-Free
+        // Monte Carlo Simulation for Arbitrary Layered BSDFs"): the random walk
+        // of `scatter` is followed and, at every scattering from the base, the
+        // light escaping next towards `wi` is added; through a rough coating
+        // the escape is evaluated after sampling the base, through a smooth
+        // coating the base is evaluated in the direction refracted towards
+        // `wi`; the random numbers of the walk are seeded from the directions
+        // and the point, so that the estimate is the same every time it is
+        // evaluated
+        let zero = Vec3::new(0.0, 0.0, 0.0);
+        let normal = if r.direction.dot(record.normal) < 0.0 {
+            record.normal
+        } else {
+            -record.normal
+        };
+        let onb = Onb::new(normal);
+        let wo = onb.to_local(-r.direction.unit());
+        let wi = onb.to_local(wi.unit());
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return zero;
+        }
+        let eta = self.ior / record.exterior_ior;
+        let absorption = spectrum::color(self.absorption, r);
+        let transmittance = |cos: f32| (absorption * (-1.0 / cos.abs().max(1e-4))).exp();
+        let smooth = self.distribution.is_smooth();
+        let seed = [
+            wo.x, wo.y, wo.z, wi.x, wi.y, wi.z, record.p.x, record.p.y, record.p.z,
+        ]
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |h, v| {
+            (h ^ v.to_bits() as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+        let mut sampler = IndependentSampler::new(seed);
+
+        // reflection from the top of a rough coating
+        let mut value = if smooth {
+            zero
+        } else {
+            let wm = (wo + wi).unit();
+            let f = fresnel_dielectric(wo.dot(wm), eta)
+                * self.distribution.d(wm)
+                * self.distribution.g(wo, wi)
+                / (4.0 * wo.z);
+            Vec3::new(f, f, f)
+        };
+
+        // the walk enters the coating as in `scatter`
+        let wm = self.distribution.sample_visible(wo, sampler.get_2d());
+        if sampler.get_1d() < fresnel_dielectric(wo.dot(wm), eta) {
+            return value;
+        }
+        let mut wt = match refract(-wo, wm, 1.0 / eta) {
+            Some(wt) if wt.z < 0.0 => wt,
+            _ => return value,
+        };
+        let mut weight =
+            Vec3::new(1.0, 1.0, 1.0) * (self.distribution.g(wo, wt) / self.distribution.g1(wo));
+
+        // direction inside a smooth coating refracted towards `wi`, seen from
+        // inside the coating with the shading frame flipped to its side
+        let up = Vec3::new(0.0, 0.0, 1.0);
+        let refracted = if smooth {
+            refract(-wi, up, 1.0 / eta).map(|w| -w)
+        } else {
+            None
+        };
+        let base_record = IntersectRecord {
+            exterior_ior: self.ior,
+            ..*record
+        };
+        for _ in 0..COATING_MAX_BOUNCES {
+            weight *= transmittance(wt.z);
+            let mut incident = Ray::new(record.p, onb.to_world(wt), r.time);
+            incident.wavelength = r.wavelength;
+            if let Some(wb) = refracted {
+                // escape through a smooth coating, which narrows the solid
+                // angle of the light refracted out of it
+                let base = self.base.eval(&incident, &base_record, onb.to_world(wb));
+                let escape =
+                    (1.0 - fresnel_dielectric(wb.z, 1.0 / eta)) * wi.z / (eta * eta * wb.z);
+                value += weight * base * transmittance(wb.z) * escape;
+            }
+
+            let (scattered, attenuation, _) =
+                match self.base.scatter(&incident, &base_record, &mut sampler) {
+                    Some(scattered) => scattered,
+                    None => break,
+                };
+            weight *= attenuation;
+            let wb = onb.to_local(scattered.direction.unit());
+            if wb.z <= 0.0 {
+                break;
+            }
+            weight *= transmittance(wb.z);
+            if !smooth {
+                value += weight * self.escape(wb, wi, eta);
+            }
+
+            // reflected back down to the base as in `scatter`; the walk ends
+            // where `scatter` escapes, as the escape is already added
+            let wm = self.distribution.sample_visible(wb, sampler.get_2d());
+            if sampler.get_1d() >= fresnel_dielectric(wb.dot(wm), 1.0 / eta) {
+                break;
+            }
+            let wr = reflect(-wb, wm);
+            if wr.z <= 0.0 {
+                break;
+            }
+            weight *= self.distribution.g(wb, wr) / self.distribution.g1(wb);
+            wt = -wr;
+        }
+        value
+    }
+
+    fn pdf(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> f32 {
+        // the random walk of `scatter` has no density in closed form; the
+        // directions it samples are weighted against other strategies by the
+        // density of reflection from the top of the coating and of
+        // cosine-weighted scattering from the base, in proportion to the
+        // fresnel reflectance
+        let normal = if r.direction.dot(record.normal) < 0.0 {
+            record.normal
+        } else {
+            -record.normal
+        };
+        let onb = Onb::new(normal);
+        let wo = onb.to_local(-r.direction.unit());
+        let wi = onb.to_local(wi.unit());
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let diffuse = wi.z * f32::consts::FRAC_1_PI;
+        if self.distribution.is_smooth() {
+            return diffuse;
+        }
+        let wm = (wo + wi).unit();
+        let specular = self.distribution.pdf(wo, wm) / (4.0 * wo.dot(wm));
+        let reflectance = fresnel_dielectric(wo.z, self.ior / record.exterior_ior);
+        reflectance * specular + (1.0 - reflectance) * diffuse
+    }
+}
+
+pub struct MixMaterial<A: Material, B: Material, T: Texture> {
+    pub a: A,
+    pub b: B,
+    pub weight: T,
+}
+
+impl<A: Material, B: Material, T: Texture> MixMaterial<A, B, T> {
+    pub fn new(a: A, b: B, weight: T) -> MixMaterial<A, B, T> {
+        // construct a blend of two materials
+        //   :a:      material where the weight is zero
+        //   :b:      material where the weight is one
+        //   :weight: texture of the weight of `b` over the surface
+        MixMaterial { a, b, weight }
+    }
+
+    fn weight(&self, record: &IntersectRecord) -> f32 {
+        self.weight
+            .value(record.u, record.v, record.p)
+            .clamp(0.0, 1.0)
+    }
+}
+
+impl<A: Material, B: Material, T: Texture> Material for MixMaterial<A, B, T> {
+    fn scatter(
+        &self,
+        r: &Ray,
+        record: &IntersectRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vec3, bool)> {
+        // the path scatters from one of the materials, picked with
+        // probability given by its weight
+        if sampler.get_1d() < self.weight(record) {
+            self.b.scatter(r, record, sampler)
+        } else {
+            self.a.scatter(r, record, sampler)
+        }
+    }
+
+    fn eval(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> Vec3 {
+        let w = self.weight(record);
+        self.a.eval(r, record, wi) * (1.0 - w) + self.b.eval(r, record, wi) * w
+    }
+
+    fn pdf(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> f32 {
+        let w = self.weight(record);
+        self.a.pdf(r, record, wi) * (1.0 - w) + self.b.pdf(r, record, wi) * w
+    }
+
+    fn emitted(&self, r: &Ray, record: &IntersectRecord) -> Vec3 {
+        let w = self.weight(record);
+        self.a.emitted(r, record) * (1.0 - w) + self.b.emitted(r, record) * w
+    }
+
+    fn interior(&self) -> Option<Interior> {
+        // paths transmitted through either material enter the same interior
+        // only if the materials agree on it; otherwise the blend is treated
+        // as a surface without interior
+        match (self.a.interior(), self.b.interior()) {
+            (Some(a), Some(b)) if a == b => Some(a),
+            _ => None,
+        }
+    }
+
+    fn dispersive(&self) -> bool {
+        self.a.dispersive() || self.b.dispersive()
+    }
+}
+
+// the principled specular lobe is clamped to a minimum roughness so that it
+// can be evaluated for arbitrary directions
+const PRINCIPLED_MIN_ROUGHNESS: f32 = 0.03;
+const CLEARCOAT_ROUGHNESS: f32 = 0.1;
+const CLEARCOAT_IOR: f32 = 1.5;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Principled {
+    pub base_color: Vec3,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub specular: f32,
+    pub sheen: f32,
+    pub clearcoat: f32,
+    pub transmission: f32,
+    pub emission: Vec3,
+}
+
+impl Principled {
+    pub fn new(base_color: Vec3) -> Principled {
+        // construct a disney-style principled material; the remaining
+        // parameters default to a rough dielectric and are set with the
+        // `with_*` methods
+        Principled {
+            base_color,
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            sheen: 0.0,
+            clearcoat: 0.0,
+            transmission: 0.0,
+            emission: Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    pub fn with_metallic(mut self, metallic: f32) -> Principled {
+        // blend from dielectric (0) to metal tinted by the base color (1)
+        self.metallic = metallic.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: f32) -> Principled {
+        // roughness in [0, 1] of the specular and transmission lobes
+        self.roughness = roughness.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_specular(mut self, specular: f32) -> Principled {
+        // dielectric reflectance at normal incidence, remapped from [0, 1] to
+        // [0, 0.08]; the default of 0.5 corresponds to an index of 1.5
+        self.specular = specular.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_sheen(mut self, sheen: f32) -> Principled {
+        // additional grazing reflection for cloth
+        self.sheen = sheen.max(0.0);
+        self
+    }
+
+    pub fn with_clearcoat(mut self, clearcoat: f32) -> Principled {
+        // strength of a glossy colorless coat over the material
+        self.clearcoat = clearcoat.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_transmission(mut self, transmission: f32) -> Principled {
+        // blend from opaque (0) to transmissive glass (1) tinted by the base color
+        self.transmission = transmission.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_emission(mut self, color: Vec3, strength: f32) -> Principled {
+        // radiance emitted from the surface
+        self.emission = color * strength;
+        self
+    }
+
+    fn ior(&self) -> f32 {
+        // refractive index with the normal reflectance given by `specular`
+        let r0 = (0.08 * self.specular).clamp(1e-4, 0.99).sqrt();
+        (1.0 + r0) / (1.0 - r0)
+    }
+
+    fn distribution(&self) -> TrowbridgeReitz {
+        let roughness = self.roughness.max(PRINCIPLED_MIN_ROUGHNESS);
+        TrowbridgeReitz::new(roughness, roughness)
+    }
+
+    fn lobe_weights(&self) -> (f32, f32) {
+        // weights of the diffuse and transmission lobes
+        let dielectric = 1.0 - self.metallic;
+        (
+            dielectric * (1.0 - self.transmission),
+            dielectric * self.transmission,
+        )
+    }
+
+    fn lobe_probabilities(&self) -> (f32, f32, f32) {
+        // probabilities of sampling the diffuse, specular and clearcoat lobes;
+        // the specular lobe also samples transmission
+        let (diffuse, _) = self.lobe_weights();
+        let specular = 1.0 - 0.5 * diffuse;
+        let clearcoat = 0.25 * self.clearcoat;
+        let total = diffuse + specular + clearcoat;
+        (diffuse / total, specular / total, clearcoat / total)
+    }
+
+    fn transmission_half_vector(wo: Vec3, wi: Vec3, eta: f32) -> Option<Vec3> {
+        // microfacet normal refracting `wo` into `wi`
+        let wm = (wo + wi * eta).unit();
+        let wm = if wm.z < 0.0 { -wm } else { wm };
+        if wm.dot(wi) >= 0.0 || wm.dot(wo) <= 0.0 {
+            return None;
+        }
+        Some(wm)
+    }
+
+    fn shading_frame(&self, r: &Ray, record: &IntersectRecord) -> (Onb, Vec3, f32, f32) {
+        // as for `Dielectric` the shading frame is flipped to the side of
+        // `wo`; returns the frame, `wo` in the flipped frame, the refractive
+        // index across the surface relative to the exterior medium and the
+        // sign of the flip
+        let onb = Onb::new(record.normal);
+        let wo = onb.to_local(-r.direction.unit());
+        if wo.z > 0.0 {
+            (onb, wo, self.ior() / record.exterior_ior, 1.0)
+        } else {
+            (onb, -wo, record.exterior_ior / self.ior(), -1.0)
+        }
+    }
+
+    fn eval_local(&self, wo: Vec3, wi: Vec3, eta: f32, base_color: Vec3) -> Vec3 {
+        // value of the bsdf times |cos(theta_i)| in the local shading frame
+        // oriented so that `wo` lies above the surface, for the base color
+        // `base_color` as represented on the path
+        let zero = Vec3::new(0.0, 0.0, 0.0);
+        let white = Vec3::new(1.0, 1.0, 1.0);
+        let (diffuse_weight, transmission_weight) = self.lobe_weights();
+        let distribution = self.distribution();
+        if wi.z > 0.0 {
+            let wm = (wo + wi).unit();
+            let cos_o = wo.dot(wm);
+
+            // lambertian diffuse with a grazing sheen
+            let sheen = white * (self.sheen * (1.0 - wi.dot(wm)).powi(5));
+            let diffuse = (base_color * f32::consts::FRAC_1_PI + sheen) * (diffuse_weight * wi.z);
+
+            // specular reflection blending dielectric and metallic fresnel
+            let fresnel = white * ((1.0 - self.metallic) * fresnel_dielectric(cos_o, eta))
+                + fresnel_schlick(base_color, cos_o) * self.metallic;
+            let specular = fresnel * (distribution.d(wm) * distribution.g(wo, wi) / (4.0 * wo.z));
+
+            // clearcoat
+            let coat = TrowbridgeReitz::new(CLEARCOAT_ROUGHNESS, CLEARCOAT_ROUGHNESS);
+            let clearcoat = white
+                * (0.25
+                    * self.clearcoat
+                    * fresnel_dielectric(cos_o, CLEARCOAT_IOR)
+                    * coat.d(wm)
+                    * coat.g(wo, wi)
+                    / (4.0 * wo.z));
+
+            diffuse + specular + clearcoat
+        } else if wi.z < 0.0 && transmission_weight > 0.0 {
+            // microfacet transmission (Walter et al.)
+            let wm = match Principled::transmission_half_vector(wo, wi, eta) {
+                Some(wm) => wm,
+                None => return zero,
+            };
+            let cos_o = wo.dot(wm);
+            let cos_i = wi.dot(wm);
+            let denom = (cos_i + cos_o / eta).powi(2);
+            let transmitted = transmission_weight * (1.0 - fresnel_dielectric(cos_o, eta));
+            base_color
+                * (transmitted
+                    * distribution.d(wm)
+                    * distribution.g(wo, wi)
+                    * (cos_i * cos_o).abs()
+                    / (wo.z * denom))
+        } else {
+            zero
+        }
+    }
+
+    fn pdf_local(&self, wo: Vec3, wi: Vec3, eta: f32) -> f32 {
+        // density of the directions `wi` sampled by `scatter` in the local
+        // shading frame oriented so that `wo` lies above the surface
+        let (diffuse_prob, specular_prob, clearcoat_prob) = self.lobe_probabilities();
+        let (_, transmission_weight) = self.lobe_weights();
+        let distribution = self.distribution();
+        let reflect_prob =
+            |cos_o: f32| 1.0 - transmission_weight * (1.0 - fresnel_dielectric(cos_o, eta));
+        if wi.z > 0.0 {
+            let wm = (wo + wi).unit();
+            let cos_o = wo.dot(wm);
+            let coat = TrowbridgeReitz::new(CLEARCOAT_ROUGHNESS, CLEARCOAT_ROUGHNESS);
+            diffuse_prob * wi.z * f32::consts::FRAC_1_PI
+                + specular_prob * distribution.pdf(wo, wm) / (4.0 * cos_o) * reflect_prob(cos_o)
+                + clearcoat_prob * coat.pdf(wo, wm) / (4.0 * cos_o)
+        } else if wi.z < 0.0 && transmission_weight > 0.0 {
+            let wm = match Principled::transmission_half_vector(wo, wi, eta) {
+                Some(wm) => wm,
+                None => return 0.0,
+            };
+            let cos_o = wo.dot(wm);
+            let cos_i = wi.dot(wm);
+            let denom = (cos_i + cos_o / eta).powi(2);
+            specular_prob * distribution.pdf(wo, wm) * cos_i.abs() / denom
+                * (1.0 - reflect_prob(cos_o))
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        r: &Ray,
+        record: &IntersectRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vec3, bool)> {
+        // pick a lobe, sample a direction from it and weight the direction by
+        // the full bsdf over the combined density of all lobes
+        let (onb, wo, eta, sign) = self.shading_frame(r, record);
+        if wo.z == 0.0 {
+            return None;
+        }
+
+        let (diffuse_prob, specular_prob, _) = self.lobe_probabilities();
+        let (_, transmission_weight) = self.lobe_weights();
+        let u = sampler.get_1d();
+        let wi = if u < diffuse_prob {
+            let (wi, _) = sampling::cosine_hemisphere(sampler.get_2d());
+            wi
+        } else if u < diffuse_prob + specular_prob {
+            let wm = self.distribution().sample_visible(wo, sampler.get_2d());
+            let reflect_prob =
+                1.0 - transmission_weight * (1.0 - fresnel_dielectric(wo.dot(wm), eta));
+            if sampler.get_1d() < reflect_prob {
+                reflect(-wo, wm)
+            } else {
+                refract(-wo, wm, 1.0 / eta)?
+            }
+        } else {
+            let coat = TrowbridgeReitz::new(CLEARCOAT_ROUGHNESS, CLEARCOAT_ROUGHNESS);
+            reflect(-wo, coat.sample_visible(wo, sampler.get_2d()))
+        };
+
+        let pdf = self.pdf_local(wo, wi, eta);
+        if pdf <= 0.0 {
+            return None;
+        }
+        let attenuation = self.eval_local(wo, wi, eta, spectrum::color(self.base_color, r)) / pdf;
+        let scattered = Ray::new(record.p, onb.to_world(wi * sign), r.time);
+        Some((scattered, attenuation, false))
+    }
+
+    fn eval(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> Vec3 {
+        let (onb, wo, eta, sign) = self.shading_frame(r, record);
+        if wo.z == 0.0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        let wi = onb.to_local(wi.unit()) * sign;
+        self.eval_local(wo, wi, eta, spectrum::color(self.base_color, r))
+    }
+
+    fn pdf(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> f32 {
+        let (onb, wo, eta, sign) = self.shading_frame(r, record);
+        if wo.z == 0.0 {
+            return 0.0;
+        }
+        self.pdf_local(wo, onb.to_local(wi.unit()) * sign, eta)
+    }
+
+    fn emitted(&self, r: &Ray, _record: &IntersectRecord) -> Vec3 {
+        spectrum::color(self.emission, r)
+    }
+
+    fn interior(&self) -> Option<Interior> {
+        // paths are only transmitted into a clear interior if the material
+        // has a transmission lobe
+        let (_, transmission_weight) = self.lobe_weights();
+        if transmission_weight <= 0.0 {
+            return None;
+        }
+        Some(Interior {
+            id: 0,
+            ior: self.ior(),
+            priority: 0,
+            absorption: Vec3::new(0.0, 0.0, 0.0),
+            scattering: Vec3::new(0.0, 0.0, 0.0),
+            anisotropy: 0.0,
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct DiffuseLight {
+    pub emission: Vec3,
+}
+
+impl DiffuseLight {
+    pub fn new(emission: Vec3) -> DiffuseLight {
+        // construct a surface emitting light uniformly from its front side;
+        // it is only sampled by shadow rays through a light of the same
+        // radiance over its surface
+        //   :emission: radiance emitted from the surface
+        DiffuseLight { emission }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _r: &Ray,
+        _record: &IntersectRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vec3, bool)> {
+        None
+    }
+
+    fn emitted(&self, r: &Ray, record: &IntersectRecord) -> Vec3 {
+        if r.direction.dot(record.normal) >= 0.0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        spectrum::color(self.emission, r)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Isotropic {
+    pub albedo: Vec3,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Vec3) -> Isotropic {
+        // phase function scattering uniformly in all directions
+        Isotropic { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        r: &Ray,
+        record: &IntersectRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vec3, bool)> {
+        // scattering inside a participating medium
+        let (direction, _) = sampling::uniform_sphere(sampler.get_2d());
+        let scattered = Ray::new(record.p, direction, r.time);
+        Some((scattered, spectrum::color(self.albedo, r), false))
+    }
+
+    fn eval(&self, r: &Ray, _record: &IntersectRecord, _wi: Vec3) -> Vec3 {
+        // the phase function has no cosine term
+        spectrum::color(self.albedo, r) * (0.25 * f32::consts::FRAC_1_PI)
+    }
+
+    fn pdf(&self, _r: &Ray, _record: &IntersectRecord, _wi: Vec3) -> f32 {
+        0.25 * f32::consts::FRAC_1_PI
+    }
+
+    fn volumetric(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct HenyeyGreenstein {
+    pub albedo: Vec3,
+    pub g: f32,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Vec3, g: f32) -> HenyeyGreenstein {
+        // anisotropic phase function; `g` in (-1, 1) is the mean cosine of the
+        // scattering angle (g > 0 scatters forwards, g < 0 backwards)
+        HenyeyGreenstein { albedo, g }
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+        &self,
+        r: &Ray,
+        record: &IntersectRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Vec3, bool)> {
+        // scattering inside a participating medium; the phase function is
+        // sampled exactly
+        let (local, _) = sampling::henyey_greenstein(sampler.get_2d(), self.g);
+        let direction = Onb::new(r.direction.unit()).to_world(local);
+        let scattered = Ray::new(record.p, direction, r.time);
+        Some((scattered, spectrum::color(self.albedo, r), false))
+    }
+
+    fn eval(&self, r: &Ray, record: &IntersectRecord, wi: Vec3) -> Vec3 {
+        spectrum::color(self.albedo, r) * self.pdf(r, record, wi)
+    }
+
+    fn pdf(&self, r: &Ray, _record: &IntersectRecord, wi: Vec3) -> f32 {
+        let g = self.g;
+        let cos_theta = r.direction.unit().dot(wi.unit());
+        let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+        0.25 * f32::consts::FRAC_1_PI * (1.0 - g * g) / (denom * denom.sqrt())
+    }
+
+    fn volumetric(&self) -> bool {
+        true
     }
 }
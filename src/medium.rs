@@ -0,0 +1,362 @@
+use std::f32;
+use std::fs;
+use std::io;
+
+use crate::aabb::Aabb;
+use crate::intersect::{IntersectEvent, IntersectRecord};
+use crate::material::Material;
+use crate::noise::Perlin;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vec::Vec3;
+
+pub struct ConstantMedium<B: IntersectEvent, M: Material> {
+    pub boundary: B,
+    pub density: f32,
+    pub phase: M,
+}
+
+impl<B: IntersectEvent, M: Material> ConstantMedium<B, M> {
+    pub fn new(boundary: B, density: f32, phase: M) -> ConstantMedium<B, M> {
+        // construct a homogeneous participating medium
+        //   :boundary: closed object enclosing the medium
+        //   :density:  extinction coefficient (per unit length)
+        //   :phase:    material scattering light at points inside the medium
+        ConstantMedium {
+            boundary,
+            density,
+            phase,
+        }
+    }
+}
+
+impl<B: IntersectEvent, M: Material> IntersectEvent for ConstantMedium<B, M> {
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
+        // find the segment of `r` inside the boundary; the boundary is also
+        // intersected behind the ray origin so that rays starting inside the
+        // medium are handled
+        let enter = self.boundary.intersect(r, -f32::MAX, f32::MAX, sampler)?;
+        let exit = self
+            .boundary
+            .intersect(r, enter.t + 1e-4, f32::MAX, sampler)?;
+        let t_enter = enter.t.max(t_min);
+        let t_exit = exit.t.min(t_max);
+        if t_enter >= t_exit {
+            return None;
+        }
+
+        // sample the free-flight distance from the exponential distribution
+        // of the medium; the ray leaves the medium unscattered if the sampled
+        // distance exceeds the distance travelled inside it
+        let length = r.direction.length();
+        let distance_inside = (t_exit - t_enter) * length;
+        let distance = -(1.0 - sampler.get_1d()).ln() / self.density;
+        if distance >= distance_inside {
+            return None;
+        }
+        let t = t_enter + distance / length;
+        let p = r.point_at_parameter(t);
+
+        // the normal is arbitrary inside a medium
+        let normal = Vec3::new(1.0, 0.0, 0.0);
+        Some(IntersectRecord::new(t, p, normal, 0.0, 0.0, &self.phase))
+    }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+        self.boundary.bounding_box(t0, t1)
+    }
+}
+
+pub struct Fog<M: Material> {
+    pub density: f32,
+    pub falloff: f32,
+    pub height: f32,
+    pub radius: f32,
+    pub phase: M,
+}
+
+impl<M: Material> Fog<M> {
+    pub fn homogeneous(density: f32, radius: f32, phase: M) -> Fog<M> {
+        // construct fog of uniform `density` filling the scene
+        Fog::height(density, 0.0, 0.0, radius, phase)
+    }
+
+    pub fn height(density: f32, falloff: f32, height: f32, radius: f32, phase: M) -> Fog<M> {
+        // construct fog filling the scene whose density decays exponentially
+        // with altitude
+        //   :density: extinction coefficient (per unit length) at `height`
+        //   :falloff: rate of exponential decay of the density with altitude
+        //   :height:  altitude of the reference density
+        //   :radius:  extent of the fog around the origin; rays leaving it
+        //             reach the environment
+        //   :phase:   material scattering light at points inside the fog
+        Fog {
+            density,
+            falloff,
+            height,
+            radius,
+            phase,
+        }
+    }
+}
+
+impl<M: Material> IntersectEvent for Fog<M> {
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
+        if self.density <= 0.0 {
+            return None;
+        }
+
+        // find the segment of `r` inside the sphere bounding the fog
+        let a = r.direction.squared_length();
+        let half_b = r.origin.dot(r.direction);
+        let c = r.origin.squared_length() - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant <= 0.0 {
+            return None;
+        }
+        let t_enter = ((-half_b - discriminant.sqrt()) / a).max(t_min);
+        let t_exit = ((-half_b + discriminant.sqrt()) / a).min(t_max);
+        if t_enter >= t_exit {
+            return None;
+        }
+
+        // sample the free-flight distance by inverting the optical depth
+        //   tau(s) = sigma * (1 - exp(-b * s)) / b
+        // from the entry point, where `sigma` is the density at the entry
+        // point and `b` the rate of change of the exponent along the ray
+        let entry = r.point_at_parameter(t_enter);
+        let sigma = self.density * (-self.falloff * (entry.y - self.height)).exp();
+        let b = self.falloff * r.direction.y;
+        let tau = -(1.0 - sampler.get_1d()).ln() / r.direction.length();
+        let s = if b.abs() < 1e-6 {
+            tau / sigma
+        } else {
+            let x = 1.0 - tau * b / sigma;
+            if x <= 0.0 {
+                return None;
+            }
+            -x.ln() / b
+        };
+        let t = t_enter + s;
+        if t >= t_exit {
+            return None;
+        }
+        let p = r.point_at_parameter(t);
+
+        // the normal is arbitrary inside a medium
+        let normal = Vec3::new(1.0, 0.0, 0.0);
+        Some(IntersectRecord::new(t, p, normal, 0.0, 0.0, &self.phase))
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        Some(Aabb::new(-r, r))
+    }
+}
+
+pub struct DensityGrid {
+    pub nx: usize,
+    pub ny: usize,
+    pub nz: usize,
+    data: Vec<f32>,
+}
+
+impl DensityGrid {
+    pub fn new(nx: usize, ny: usize, nz: usize, data: Vec<f32>) -> DensityGrid {
+        // construct dense grid of `nx` by `ny` by `nz` voxel densities, stored
+        // with x varying fastest and z slowest
+        assert_eq!(data.len(), nx * ny * nz, "density grid size mismatch");
+        DensityGrid { nx, ny, nz, data }
+    }
+
+    pub fn load(path: &str) -> io::Result<DensityGrid> {
+        // read a raw grid file: the dimensions `nx`, `ny`, `nz` as little-endian
+        // u32 followed by `nx * ny * nz` little-endian f32 densities
+        let bytes = fs::read(path)?;
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        if bytes.len() < 12 {
+            return Err(invalid("density grid header is truncated"));
+        }
+        let word = |i: usize| {
+            [
+                bytes[4 * i],
+                bytes[4 * i + 1],
+                bytes[4 * i + 2],
+                bytes[4 * i + 3],
+            ]
+        };
+        let nx = u32::from_le_bytes(word(0)) as usize;
+        let ny = u32::from_le_bytes(word(1)) as usize;
+        let nz = u32::from_le_bytes(word(2)) as usize;
+        if nx == 0 || ny == 0 || nz == 0 {
+            return Err(invalid("density grid has an empty dimension"));
+        }
+        let count = nx
+            .checked_mul(ny)
+            .and_then(|count| count.checked_mul(nz))
+            .filter(|&count| count <= usize::MAX / 4)
+            .ok_or_else(|| invalid("density grid dimensions overflow"))?;
+        if bytes.len() - 12 != 4 * count {
+            return Err(invalid("density grid size does not match its header"));
+        }
+        let data = (0..count)
+            .map(|i| f32::from_le_bytes(word(3 + i)).max(0.0))
+            .collect();
+        Ok(DensityGrid::new(nx, ny, nz, data))
+    }
+
+    pub fn from_noise(n: usize, noise: &Perlin, frequency: f32, octaves: u32) -> DensityGrid {
+        // generate a cloud-like `n` by `n` by `n` grid: fractal noise eroding a
+        // spherical puff that fades out towards the grid boundary
+        let mut data = Vec::with_capacity(n * n * n);
+        for k in 0..n {
+            for j in 0..n {
+                for i in 0..n {
+                    let p = Vec3::new(
+                        (i as f32 + 0.5) / n as f32,
+                        (j as f32 + 0.5) / n as f32,
+                        (k as f32 + 0.5) / n as f32,
+                    );
+                    let falloff = 1.0 - ((p - Vec3::new(0.5, 0.5, 0.5)) * 2.0).squared_length();
+                    let detail = noise.fbm(p * frequency, octaves);
+                    data.push((falloff + 0.6 * detail - 0.1).max(0.0));
+                }
+            }
+        }
+        DensityGrid::new(n, n, n, data)
+    }
+
+    pub fn max(&self) -> f32 {
+        self.data.iter().cloned().fold(0.0, f32::max)
+    }
+
+    fn value(&self, i: usize, j: usize, k: usize) -> f32 {
+        self.data[(k * self.ny + j) * self.nx + i]
+    }
+
+    pub fn lookup(&self, p: Vec3) -> f32 {
+        // trilinear interpolation of the voxel densities at `p` in [0, 1]^3;
+        // voxel values are located at the voxel centers
+        let axis = |x: f32, n: usize| {
+            let g = (x * n as f32 - 0.5).max(0.0).min(n as f32 - 1.0);
+            let i = (g as usize).min(n.saturating_sub(2));
+            (i, (i + 1).min(n - 1), g - i as f32)
+        };
+        let (i0, i1, fx) = axis(p.x, self.nx);
+        let (j0, j1, fy) = axis(p.y, self.ny);
+        let (k0, k1, fz) = axis(p.z, self.nz);
+        let lerp = |t: f32, a: f32, b: f32| a + t * (b - a);
+        lerp(
+            fz,
+            lerp(
+                fy,
+                lerp(fx, self.value(i0, j0, k0), self.value(i1, j0, k0)),
+                lerp(fx, self.value(i0, j1, k0), self.value(i1, j1, k0)),
+            ),
+            lerp(
+                fy,
+                lerp(fx, self.value(i0, j0, k1), self.value(i1, j0, k1)),
+                lerp(fx, self.value(i0, j1, k1), self.value(i1, j1, k1)),
+            ),
+        )
+    }
+}
+
+pub struct GridMedium<M: Material> {
+    pub bounds: Aabb,
+    pub grid: DensityGrid,
+    pub density: f32,
+    pub phase: M,
+    max_density: f32,
+}
+
+impl<M: Material> GridMedium<M> {
+    pub fn new(bounds: Aabb, grid: DensityGrid, density: f32, phase: M) -> GridMedium<M> {
+        // construct a heterogeneous participating medium
+        //   :bounds:  box the grid is stretched over
+        //   :grid:    voxel densities
+        //   :density: scale converting grid values to extinction coefficients
+        //   :phase:   material scattering light at points inside the medium
+        let max_density = grid.max() * density;
+        GridMedium {
+            bounds,
+            grid,
+            density,
+            phase,
+            max_density,
+        }
+    }
+
+    fn density_at(&self, p: Vec3) -> f32 {
+        // extinction coefficient at world space point `p`
+        let extent = self.bounds.max - self.bounds.min;
+        self.grid.lookup((p - self.bounds.min) / extent) * self.density
+    }
+}
+
+impl<M: Material> IntersectEvent for GridMedium<M> {
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
+        // delta tracking: sample tentative collisions against the majorant
+        // `max_density` and accept each as a real collision with probability
+        // density / `max_density`
+        let (t_enter, t_exit) = self.bounds.hit_interval(r, t_min, t_max)?;
+        if self.max_density <= 0.0 {
+            return None;
+        }
+        let step = self.max_density * r.direction.length();
+        let mut t = t_enter;
+        loop {
+            t -= (1.0 - sampler.get_1d()).ln() / step;
+            if t >= t_exit {
+                return None;
+            }
+            let p = r.point_at_parameter(t);
+            if sampler.get_1d() * self.max_density < self.density_at(p) {
+                // the normal is arbitrary inside a medium
+                let normal = Vec3::new(1.0, 0.0, 0.0);
+                return Some(IntersectRecord::new(t, p, normal, 0.0, 0.0, &self.phase));
+            }
+        }
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+
+    fn transmittance(&self, r: &Ray, t_min: f32, t_max: f32, sampler: &mut dyn Sampler) -> f32 {
+        // unbiased estimate of the transmittance along `r` between `t_min` and
+        // `t_max` by ratio tracking against the majorant `max_density`
+        let (t_enter, t_exit) = match self.bounds.hit_interval(r, t_min, t_max) {
+            Some(interval) if self.max_density > 0.0 => interval,
+            _ => return 1.0,
+        };
+        let step = self.max_density * r.direction.length();
+        let mut transmittance = 1.0;
+        let mut t = t_enter;
+        loop {
+            t -= (1.0 - sampler.get_1d()).ln() / step;
+            if t >= t_exit {
+                return transmittance;
+            }
+            transmittance *= 1.0 - self.density_at(r.point_at_parameter(t)) / self.max_density;
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::f32;
+
+use crate::vec::Vec3;
+
+// microfacet distributions are evaluated in a local shading frame in which the
+// macrosurface normal is the z axis
+
+#[derive(Debug, Copy, Clone)]
+pub struct TrowbridgeReitz {
+    pub alpha_x: f32,
+    pub alpha_y: f32,
+}
+
+impl TrowbridgeReitz {
+    pub fn new(roughness_x: f32, roughness_y: f32) -> TrowbridgeReitz {
+        // construct GGX / trowbridge-reitz distribution; roughness in [0, 1]
+        // along the x and y axes of the shading frame is remapped to the
+        // distribution width by squaring it for a perceptually linear response
+        TrowbridgeReitz {
+            alpha_x: roughness_x.clamp(0.0, 1.0).powi(2),
+            alpha_y: roughness_y.clamp(0.0, 1.0).powi(2),
+        }
+    }
+
+    pub fn is_smooth(&self) -> bool {
+        // whether the distribution is too narrow to be evaluated; it then
+        // scatters like a perfectly smooth surface
+        self.alpha_x * self.alpha_y < 1e-6
+    }
+
+    pub fn d(&self, wm: Vec3) -> f32 {
+        // density of microfacet normals `wm` per unit projected area
+        if wm.z <= 0.0 {
+            return 0.0;
+        }
+        let e = (wm.x / self.alpha_x).powi(2) + (wm.y / self.alpha_y).powi(2) + wm.z * wm.z;
+        1.0 / (f32::consts::PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    pub fn pdf(&self, wo: Vec3, wm: Vec3) -> f32 {
+        // density of the microfacet normals `wm` sampled by `sample_visible`
+        self.g1(wo) * wo.dot(wm).max(0.0) * self.d(wm) / wo.z.abs()
+    }
+
+    pub fn lambda(&self, w: Vec3) -> f32 {
+        // smith auxiliary function for direction `w`
+        if w.z == 0.0 {
+            return f32::MAX;
+        }
+        let alpha2_tan2 =
+            ((self.alpha_x * w.x).powi(2) + (self.alpha_y * w.y).powi(2)) / (w.z * w.z);
+        0.5 * ((1.0 + alpha2_tan2).sqrt() - 1.0)
+    }
+
+    pub fn g1(&self, w: Vec3) -> f32 {
+        // smith masking function
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    pub fn g(&self, wo: Vec3, wi: Vec3) -> f32 {
+        // height-correlated smith masking-shadowing function
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    pub fn sample_visible(&self, wo: Vec3, u: (f32, f32)) -> Vec3 {
+        // sample a microfacet normal from the distribution of normals visible
+        // from `wo` (Heitz, "Sampling the GGX Distribution of Visible Normals")
+        let vh = Vec3::new(self.alpha_x * wo.x, self.alpha_y * wo.y, wo.z).unit();
+
+        // orthonormal basis around the stretched view direction
+        let length2 = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if length2 > 0.0 {
+            Vec3::new(-vh.y, vh.x, 0.0) / length2.sqrt()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = vh.cross(t1);
+
+        // sample the projected area of the visible hemisphere
+        let r = u.0.sqrt();
+        let phi = 2.0 * f32::consts::PI * u.1;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = t1 * p1 + t2 * p2 + vh * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+
+        // unstretch back to the microfacet normal
+        Vec3::new(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(0.0)).unit()
+    }
+}
@@ -0,0 +1,106 @@
+use rand::seq::SliceRandom;
+
+use crate::vec::Vec3;
+
+fn fade(t: f32) -> f32 {
+    // quintic interpolant 6t^5 - 15t^4 + 10t^3
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f32, a: f32, b: f32) -> f32 {
+    a + t * (b - a)
+}
+
+fn gradient(hash: u8, x: f32, y: f32, z: f32) -> f32 {
+    // dot product of (x, y, z) with one of 12 gradient directions
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+pub struct Perlin {
+    perm: [u8; 512],
+}
+
+impl Perlin {
+    pub fn new(rng: &mut rand_pcg::Pcg64) -> Perlin {
+        // gradient noise with a permutation table shuffled by `rng`
+        // (Perlin, "Improving Noise")
+        let mut p: Vec<u8> = (0..=255).collect();
+        p.shuffle(rng);
+        let mut perm = [0; 512];
+        for (i, value) in perm.iter_mut().enumerate() {
+            *value = p[i & 255];
+        }
+        Perlin { perm }
+    }
+
+    pub fn noise(&self, p: Vec3) -> f32 {
+        // return noise at `p`, approximately in [-1, 1]
+        let (xf, yf, zf) = (p.x.floor(), p.y.floor(), p.z.floor());
+        let xi = (xf as i32 & 255) as usize;
+        let yi = (yf as i32 & 255) as usize;
+        let zi = (zf as i32 & 255) as usize;
+        let (x, y, z) = (p.x - xf, p.y - yf, p.z - zf);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let perm = &self.perm;
+        let a = perm[xi] as usize + yi;
+        let aa = perm[a] as usize + zi;
+        let ab = perm[a + 1] as usize + zi;
+        let b = perm[xi + 1] as usize + yi;
+        let ba = perm[b] as usize + zi;
+        let bb = perm[b + 1] as usize + zi;
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(
+                    u,
+                    gradient(perm[aa], x, y, z),
+                    gradient(perm[ba], x - 1.0, y, z),
+                ),
+                lerp(
+                    u,
+                    gradient(perm[ab], x, y - 1.0, z),
+                    gradient(perm[bb], x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    gradient(perm[aa + 1], x, y, z - 1.0),
+                    gradient(perm[ba + 1], x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    gradient(perm[ab + 1], x, y - 1.0, z - 1.0),
+                    gradient(perm[bb + 1], x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+
+    pub fn fbm(&self, p: Vec3, octaves: u32) -> f32 {
+        // fractal sum of `octaves` octaves of noise, each at double the
+        // frequency and half the amplitude of the previous one
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        for _ in 0..octaves {
+            sum += amplitude * self.noise(p * frequency);
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        sum
+    }
+}
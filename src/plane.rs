@@ -0,0 +1,121 @@
+use std::f32;
+
+use crate::aabb::Aabb;
+use crate::intersect::{IntersectEvent, IntersectRecord};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vec::{Onb, Vec3};
+
+fn plane_hit(r: &Ray, point: Vec3, normal: Vec3, t_min: f32, t_max: f32) -> Option<(f32, Vec3)> {
+    // return the timestep and point at which `r` crosses the plane through
+    // `point` with `normal`
+    let denom = r.direction.dot(normal);
+    if denom.abs() < 1e-8 {
+        return None;
+    }
+    let t = (point - r.origin).dot(normal) / denom;
+    if t_min < t && t < t_max {
+        Some((t, r.point_at_parameter(t)))
+    } else {
+        None
+    }
+}
+
+pub struct Plane<M: Material> {
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub material: M,
+    onb: Onb,
+}
+
+impl<M: Material> Plane<M> {
+    pub fn new(point: Vec3, normal: Vec3, material: M) -> Plane<M> {
+        // construct infinite plane through `point` with `normal`
+        let normal = normal.unit();
+        Plane {
+            point,
+            normal,
+            material,
+            onb: Onb::new(normal),
+        }
+    }
+}
+
+impl<M: Material> IntersectEvent for Plane<M> {
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
+        let (t, p) = plane_hit(r, self.point, self.normal, t_min, t_max)?;
+        // surface coordinates are distances from `point` along the plane axes
+        let offset = p - self.point;
+        let u = offset.dot(self.onb.u);
+        let v = offset.dot(self.onb.v);
+        Some(IntersectRecord::new(t, p, self.normal, u, v, &self.material).with_tangent(self.onb.u))
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        None
+    }
+}
+
+pub struct Disk<M: Material> {
+    pub center: Vec3,
+    pub normal: Vec3,
+    pub radius: f32,
+    pub material: M,
+    onb: Onb,
+}
+
+impl<M: Material> Disk<M> {
+    pub fn new(center: Vec3, normal: Vec3, radius: f32, material: M) -> Disk<M> {
+        // construct disk of `radius` around `center`, facing `normal`
+        let normal = normal.unit();
+        Disk {
+            center,
+            normal,
+            radius,
+            material,
+            onb: Onb::new(normal),
+        }
+    }
+}
+
+impl<M: Material> IntersectEvent for Disk<M> {
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
+        let (t, p) = plane_hit(r, self.center, self.normal, t_min, t_max)?;
+        let offset = p - self.center;
+        let distance = offset.length();
+        if distance > self.radius {
+            return None;
+        }
+        // surface coordinates are the polar angle (scaled to [0, 1]) and the
+        // relative distance from the center
+        let phi = offset.dot(self.onb.v).atan2(offset.dot(self.onb.u));
+        let u = (phi + f32::consts::PI) / (2.0 * f32::consts::PI);
+        let v = distance / self.radius;
+        let tangent = self.normal.cross(offset);
+        Some(IntersectRecord::new(t, p, self.normal, u, v, &self.material).with_tangent(tangent))
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        // extent of the disk along each axis, padded to keep the box non-degenerate
+        let n = self.normal;
+        let extent = Vec3::new(
+            self.radius * (1.0 - n.x * n.x).max(0.0).sqrt() + 1e-4,
+            self.radius * (1.0 - n.y * n.y).max(0.0).sqrt() + 1e-4,
+            self.radius * (1.0 - n.z * n.z).max(0.0).sqrt() + 1e-4,
+        );
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
+}
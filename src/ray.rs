@@ -4,11 +4,23 @@ use crate::vec::Vec3;
 pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
+    pub time: f32,
+    pub wavelength: Option<f32>,
 }
 
 impl Ray {
-    pub fn new(origin: Vec3, direction: Vec3) -> Ray {
-        Ray { origin, direction }
+    pub fn new(origin: Vec3, direction: Vec3, time: f32) -> Ray {
+        // construct ray
+        //   :origin:    ray origin
+        //   :direction: ray direction (not necessarily unit length)
+        //   :time:      time within the camera shutter interval at which the ray is traced
+        // the hero wavelength of the path (in spectral mode) is set by the integrator
+        Ray {
+            origin,
+            direction,
+            time,
+            wavelength: None,
+        }
     }
 
     pub fn point_at_parameter(self, t: f32) -> Vec3 {
@@ -0,0 +1,261 @@
+use crate::aabb::Aabb;
+use crate::intersect::{FlipNormals, IntersectEvent, IntersectList, IntersectRecord};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sampler::Sampler;
+use crate::vec::Vec3;
+
+// half-thickness given to the bounding boxes of rectangles
+const PAD: f32 = 1e-4;
+
+pub struct XYRect<M: Material> {
+    pub x0: f32,
+    pub x1: f32,
+    pub y0: f32,
+    pub y1: f32,
+    pub k: f32,
+    pub material: M,
+}
+
+impl<M: Material> XYRect<M> {
+    pub fn new(x0: f32, x1: f32, y0: f32, y1: f32, k: f32, material: M) -> XYRect<M> {
+        // construct rectangle [x0, x1] x [y0, y1] in the plane z = k
+        // with normal +z
+        XYRect {
+            x0,
+            x1,
+            y0,
+            y1,
+            k,
+            material,
+        }
+    }
+}
+
+impl<M: Material> IntersectEvent for XYRect<M> {
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
+        let t = (self.k - r.origin.z) / r.direction.z;
+        if !(t_min < t && t < t_max) {
+            return None;
+        }
+        let p = r.point_at_parameter(t);
+        if p.x < self.x0 || p.x > self.x1 || p.y < self.y0 || p.y > self.y1 {
+            return None;
+        }
+        let u = (p.x - self.x0) / (self.x1 - self.x0);
+        let v = (p.y - self.y0) / (self.y1 - self.y0);
+        let normal = Vec3::new(0.0, 0.0, 1.0);
+        Some(
+            IntersectRecord::new(t, p, normal, u, v, &self.material)
+                .with_tangent(Vec3::new(1.0, 0.0, 0.0)),
+        )
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        Some(Aabb::new(
+            Vec3::new(self.x0, self.y0, self.k - PAD),
+            Vec3::new(self.x1, self.y1, self.k + PAD),
+        ))
+    }
+}
+
+pub struct XZRect<M: Material> {
+    pub x0: f32,
+    pub x1: f32,
+    pub z0: f32,
+    pub z1: f32,
+    pub k: f32,
+    pub material: M,
+}
+
+impl<M: Material> XZRect<M> {
+    pub fn new(x0: f32, x1: f32, z0: f32, z1: f32, k: f32, material: M) -> XZRect<M> {
+        // construct rectangle [x0, x1] x [z0, z1] in the plane y = k
+        // with normal +y
+        XZRect {
+            x0,
+            x1,
+            z0,
+            z1,
+            k,
+            material,
+        }
+    }
+}
+
+impl<M: Material> IntersectEvent for XZRect<M> {
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
+        let t = (self.k - r.origin.y) / r.direction.y;
+        if !(t_min < t && t < t_max) {
+            return None;
+        }
+        let p = r.point_at_parameter(t);
+        if p.x < self.x0 || p.x > self.x1 || p.z < self.z0 || p.z > self.z1 {
+            return None;
+        }
+        let u = (p.x - self.x0) / (self.x1 - self.x0);
+        let v = (p.z - self.z0) / (self.z1 - self.z0);
+        let normal = Vec3::new(0.0, 1.0, 0.0);
+        Some(
+            IntersectRecord::new(t, p, normal, u, v, &self.material)
+                .with_tangent(Vec3::new(1.0, 0.0, 0.0)),
+        )
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        Some(Aabb::new(
+            Vec3::new(self.x0, self.k - PAD, self.z0),
+            Vec3::new(self.x1, self.k + PAD, self.z1),
+        ))
+    }
+}
+
+pub struct YZRect<M: Material> {
+    pub y0: f32,
+    pub y1: f32,
+    pub z0: f32,
+    pub z1: f32,
+    pub k: f32,
+    pub material: M,
+}
+
+impl<M: Material> YZRect<M> {
+    pub fn new(y0: f32, y1: f32, z0: f32, z1: f32, k: f32, material: M) -> YZRect<M> {
+        // construct rectangle [y0, y1] x [z0, z1] in the plane x = k
+        // with normal +x
+        YZRect {
+            y0,
+            y1,
+            z0,
+            z1,
+            k,
+            material,
+        }
+    }
+}
+
+impl<M: Material> IntersectEvent for YZRect<M> {
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
+        let t = (self.k - r.origin.x) / r.direction.x;
+        if !(t_min < t && t < t_max) {
+            return None;
+        }
+        let p = r.point_at_parameter(t);
+        if p.y < self.y0 || p.y > self.y1 || p.z < self.z0 || p.z > self.z1 {
+            return None;
+        }
+        let u = (p.y - self.y0) / (self.y1 - self.y0);
+        let v = (p.z - self.z0) / (self.z1 - self.z0);
+        let normal = Vec3::new(1.0, 0.0, 0.0);
+        Some(
+            IntersectRecord::new(t, p, normal, u, v, &self.material)
+                .with_tangent(Vec3::new(0.0, 1.0, 0.0)),
+        )
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        Some(Aabb::new(
+            Vec3::new(self.k - PAD, self.y0, self.z0),
+            Vec3::new(self.k + PAD, self.y1, self.z1),
+        ))
+    }
+}
+
+pub struct Cuboid {
+    sides: IntersectList,
+}
+
+impl Cuboid {
+    pub fn new<M: Material + Clone + 'static>(min: Vec3, max: Vec3, material: M) -> Cuboid {
+        // construct an axis-aligned box spanning corners `min` and `max`
+        // from six rectangles with outward facing normals
+        let sides: Vec<Box<dyn IntersectEvent>> = vec![
+            Box::new(XYRect::new(
+                min.x,
+                max.x,
+                min.y,
+                max.y,
+                max.z,
+                material.clone(),
+            )),
+            Box::new(FlipNormals::new(XYRect::new(
+                min.x,
+                max.x,
+                min.y,
+                max.y,
+                min.z,
+                material.clone(),
+            ))),
+            Box::new(XZRect::new(
+                min.x,
+                max.x,
+                min.z,
+                max.z,
+                max.y,
+                material.clone(),
+            )),
+            Box::new(FlipNormals::new(XZRect::new(
+                min.x,
+                max.x,
+                min.z,
+                max.z,
+                min.y,
+                material.clone(),
+            ))),
+            Box::new(YZRect::new(
+                min.y,
+                max.y,
+                min.z,
+                max.z,
+                max.x,
+                material.clone(),
+            )),
+            Box::new(FlipNormals::new(YZRect::new(
+                min.y, max.y, min.z, max.z, min.x, material,
+            ))),
+        ];
+        Cuboid {
+            sides: IntersectList::new(sides),
+        }
+    }
+}
+
+impl IntersectEvent for Cuboid {
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
+        // the sides are one object, bounding a single interior
+        self.sides
+            .intersect(r, t_min, t_max, sampler)
+            .map(|record| IntersectRecord {
+                object: 0,
+                ..record
+            })
+    }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+        self.sides.bounding_box(t0, t1)
+    }
+}
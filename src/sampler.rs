@@ -0,0 +1,350 @@
+use std::str::FromStr;
+
+use rand::{Rng, SeedableRng};
+
+// prime bases for the halton sequence; dimensions beyond the table fall back
+// to hashed random values
+const PRIMES: [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+// largest f32 strictly less than 1.0
+const ONE_MINUS_EPSILON: f32 = 0.999_999_94;
+
+fn hash(mut x: u32) -> u32 {
+    // integer hash with good avalanche behaviour (lowbias32)
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb_352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846c_a68b);
+    x ^= x >> 16;
+    x
+}
+
+fn hash_combine(seed: u32, v: u32) -> u32 {
+    // combine `v` into the hash `seed`
+    hash(
+        seed ^ v
+            .wrapping_add(0x9e37_79b9)
+            .wrapping_add(seed << 6)
+            .wrapping_add(seed >> 2),
+    )
+}
+
+fn to_unit_float(x: u32) -> f32 {
+    // map the 24 most significant bits of `x` into [0, 1)
+    (x >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+}
+
+fn permute(mut i: u32, l: u32, p: u32) -> u32 {
+    // return element `i` of a random permutation of [0, l) selected by `p`
+    // (Kensler, "Correlated Multi-Jittered Sampling")
+    let mut w = l - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < l {
+            break;
+        }
+    }
+    (i.wrapping_add(p)) % l
+}
+
+fn radical_inverse(base: u32, mut index: u32) -> f32 {
+    // mirror the base `base` digits of `index` about the radix point
+    let inv_base = 1.0 / base as f32;
+    let mut inv_base_n = 1.0;
+    let mut reversed: u64 = 0;
+    while index > 0 {
+        let next = index / base;
+        let digit = index - next * base;
+        reversed = reversed * base as u64 + digit as u64;
+        inv_base_n *= inv_base;
+        index = next;
+    }
+    (reversed as f32 * inv_base_n).min(ONE_MINUS_EPSILON)
+}
+
+fn sobol_0(index: u32) -> u32 {
+    // first dimension of the sobol sequence (van der corput in base 2)
+    index.reverse_bits()
+}
+
+fn sobol_1(mut index: u32) -> u32 {
+    // second dimension of the sobol sequence
+    let mut v = 1u32 << 31;
+    let mut result = 0;
+    while index != 0 {
+        if index & 1 != 0 {
+            result ^= v;
+        }
+        index >>= 1;
+        v ^= v >> 1;
+    }
+    result
+}
+
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    // owen scrambling of the bits of `x` (Burley, "Practical Hash-based Owen Scrambling")
+    let mut x = x.reverse_bits();
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x.reverse_bits()
+}
+
+pub trait Sampler {
+    // begin sample `index` of pixel (`px`, `py`); every following request for
+    // a sample dimension is taken from this pixel sample
+    fn start_sample(&mut self, px: u32, py: u32, index: u32);
+    fn get_1d(&mut self) -> f32;
+    fn get_2d(&mut self) -> (f32, f32);
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum SamplerType {
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+impl SamplerType {
+    pub fn create(self, ns: u32, seed: u64) -> Box<dyn Sampler> {
+        // construct a sampler of this type
+        //   :ns:   samples per pixel
+        //   :seed: seed used to decorrelate the sampler
+        match self {
+            SamplerType::Independent => Box::new(IndependentSampler::new(seed)),
+            SamplerType::Stratified => Box::new(StratifiedSampler::new(ns, seed as u32)),
+            SamplerType::Halton => Box::new(HaltonSampler::new(seed as u32)),
+            SamplerType::Sobol => Box::new(SobolSampler::new(seed as u32)),
+        }
+    }
+}
+
+impl FromStr for SamplerType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<SamplerType, String> {
+        match s {
+            "independent" => Ok(SamplerType::Independent),
+            "stratified" => Ok(SamplerType::Stratified),
+            "halton" => Ok(SamplerType::Halton),
+            "sobol" => Ok(SamplerType::Sobol),
+            _ => Err(format!("unknown sampler: {}", s)),
+        }
+    }
+}
+
+pub struct IndependentSampler {
+    rng: rand_pcg::Pcg64,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> IndependentSampler {
+        // uniform random samples, independent across dimensions
+        IndependentSampler {
+            rng: rand_pcg::Pcg64::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_sample(&mut self, _px: u32, _py: u32, _index: u32) {}
+
+    fn get_1d(&mut self) -> f32 {
+        self.rng.gen::<f32>()
+    }
+
+    fn get_2d(&mut self) -> (f32, f32) {
+        (self.rng.gen::<f32>(), self.rng.gen::<f32>())
+    }
+}
+
+pub struct StratifiedSampler {
+    ns: u32,
+    n: u32,
+    seed: u32,
+    pixel: u32,
+    index: u32,
+    dimension: u32,
+}
+
+impl StratifiedSampler {
+    pub fn new(ns: u32, seed: u32) -> StratifiedSampler {
+        // jittered samples; each dimension of a pixel is split into `ns`
+        // strata which are assigned to the pixel samples in a shuffled order;
+        // 2D dimensions are split into a square grid of `n` by `n` strata
+        // with `n` the integer square root of `ns`, and the samples beyond
+        // the `n * n` of the grid are placed in randomly chosen extra strata
+        // of the grid
+        assert!(ns > 0, "stratified sampler requires at least one sample");
+        StratifiedSampler {
+            ns,
+            n: ns.isqrt(),
+            seed,
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    fn next_hash(&mut self) -> u32 {
+        // hash identifying the current pixel and dimension
+        let h = hash_combine(hash_combine(self.seed, self.pixel), self.dimension);
+        self.dimension += 1;
+        h
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_sample(&mut self, px: u32, py: u32, index: u32) {
+        self.pixel = hash_combine(px, py);
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let h = self.next_hash();
+        let stratum = permute(self.index % self.ns, self.ns, h);
+        let jitter = to_unit_float(hash_combine(h, self.index));
+        ((stratum as f32 + jitter) / self.ns as f32).min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> (f32, f32) {
+        let h = self.next_hash();
+        let n = self.n;
+        let mut stratum = permute(self.index % self.ns, self.ns, h);
+        if stratum >= n * n {
+            // extra sample, in a stratum of the grid shuffled independently
+            stratum = permute((stratum - n * n) % (n * n), n * n, hash(h));
+        }
+        let jx = to_unit_float(hash_combine(h, 2 * self.index));
+        let jy = to_unit_float(hash_combine(h, 2 * self.index + 1));
+        (
+            (((stratum % n) as f32 + jx) / n as f32).min(ONE_MINUS_EPSILON),
+            (((stratum / n) as f32 + jy) / n as f32).min(ONE_MINUS_EPSILON),
+        )
+    }
+}
+
+pub struct HaltonSampler {
+    seed: u32,
+    pixel: u32,
+    index: u32,
+    dimension: u32,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u32) -> HaltonSampler {
+        // halton sequence, decorrelated between pixels by a per-pixel
+        // cranley-patterson rotation of every dimension
+        HaltonSampler {
+            seed,
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_sample(&mut self, px: u32, py: u32, index: u32) {
+        self.pixel = hash_combine(self.seed, hash_combine(px, py));
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let h = hash_combine(self.pixel, self.dimension);
+        let d = self.dimension as usize;
+        self.dimension += 1;
+        if d >= PRIMES.len() {
+            return to_unit_float(hash_combine(h, self.index));
+        }
+        let x = radical_inverse(PRIMES[d], self.index) + to_unit_float(h);
+        if x >= 1.0 {
+            (x - 1.0).min(ONE_MINUS_EPSILON)
+        } else {
+            x
+        }
+    }
+
+    fn get_2d(&mut self) -> (f32, f32) {
+        (self.get_1d(), self.get_1d())
+    }
+}
+
+pub struct SobolSampler {
+    seed: u32,
+    pixel: u32,
+    index: u32,
+    dimension: u32,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u32) -> SobolSampler {
+        // owen-scrambled sobol sequence; every dimension is padded from the
+        // first two sobol dimensions with an independently shuffled index and
+        // independent scrambling
+        SobolSampler {
+            seed,
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    fn next_seed(&mut self) -> (u32, u32) {
+        // return a shuffled sample index and a scrambling seed for the current dimension
+        let h = hash_combine(self.pixel, self.dimension);
+        self.dimension += 1;
+        (nested_uniform_scramble(self.index, h), hash(h))
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_sample(&mut self, px: u32, py: u32, index: u32) {
+        self.pixel = hash_combine(self.seed, hash_combine(px, py));
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let (index, seed) = self.next_seed();
+        to_unit_float(nested_uniform_scramble(sobol_0(index), seed))
+    }
+
+    fn get_2d(&mut self) -> (f32, f32) {
+        let (index, seed) = self.next_seed();
+        (
+            to_unit_float(nested_uniform_scramble(sobol_0(index), seed)),
+            to_unit_float(nested_uniform_scramble(sobol_1(index), hash(seed))),
+        )
+    }
+}
@@ -0,0 +1,80 @@
+use std::f32;
+
+use crate::vec::Vec3;
+
+// every routine maps a uniform 2D sample in [0, 1)^2 to a point and returns it
+// together with its probability density; directions are returned in a local
+// frame with the `z` axis as the pole (see `Onb`)
+
+pub fn concentric_disk(u: (f32, f32)) -> (Vec3, f32) {
+    // sample a point on the unit disk (Shirley & Chiu concentric mapping)
+    // pdf is with respect to area
+    let ox = 2.0 * u.0 - 1.0;
+    let oy = 2.0 * u.1 - 1.0;
+    if ox == 0.0 && oy == 0.0 {
+        return (Vec3::new(0.0, 0.0, 0.0), f32::consts::FRAC_1_PI);
+    }
+    let (r, theta) = if ox.abs() > oy.abs() {
+        (ox, f32::consts::FRAC_PI_4 * (oy / ox))
+    } else {
+        (
+            oy,
+            f32::consts::FRAC_PI_2 - f32::consts::FRAC_PI_4 * (ox / oy),
+        )
+    };
+    (
+        Vec3::new(r * theta.cos(), r * theta.sin(), 0.0),
+        f32::consts::FRAC_1_PI,
+    )
+}
+
+pub fn cosine_hemisphere(u: (f32, f32)) -> (Vec3, f32) {
+    // sample a direction on the hemisphere about `z` proportional to cos(theta)
+    // pdf is with respect to solid angle
+    let (d, _) = concentric_disk(u);
+    let z = (1.0 - d.x * d.x - d.y * d.y).max(0.0).sqrt();
+    (Vec3::new(d.x, d.y, z), z * f32::consts::FRAC_1_PI)
+}
+
+pub fn uniform_sphere(u: (f32, f32)) -> (Vec3, f32) {
+    // sample a direction uniformly over the unit sphere
+    // pdf is with respect to solid angle
+    let z = 1.0 - 2.0 * u.0;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * f32::consts::PI * u.1;
+    (
+        Vec3::new(r * phi.cos(), r * phi.sin(), z),
+        0.25 * f32::consts::FRAC_1_PI,
+    )
+}
+
+pub fn uniform_cone(u: (f32, f32), cos_max: f32) -> (Vec3, f32) {
+    // sample a direction uniformly inside the cone about `z` with half-angle
+    // acos(`cos_max`); pdf is with respect to solid angle
+    let z = 1.0 - u.0 * (1.0 - cos_max);
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * f32::consts::PI * u.1;
+    (
+        Vec3::new(r * phi.cos(), r * phi.sin(), z),
+        1.0 / (2.0 * f32::consts::PI * (1.0 - cos_max)),
+    )
+}
+
+pub fn henyey_greenstein(u: (f32, f32), g: f32) -> (Vec3, f32) {
+    // sample a direction from the henyey-greenstein phase function with mean
+    // cosine `g` about the direction of propagation `z`, by inverting the cdf
+    // of the cosine; pdf is with respect to solid angle
+    let cos_theta = if g.abs() < 1e-3 {
+        1.0 - 2.0 * u.0
+    } else {
+        let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u.0);
+        ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+    };
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * f32::consts::PI * u.1;
+    let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+    (
+        Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta),
+        0.25 * f32::consts::FRAC_1_PI * (1.0 - g * g) / (denom * denom.sqrt()),
+    )
+}
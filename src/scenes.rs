@@ -1,11 +1,31 @@
+use std::rc::Rc;
+
 use rand::Rng;
 use rand_pcg;
 
+use crate::aabb::Aabb;
+use crate::bvh::Bvh;
 use crate::camera::Camera;
-use crate::intersect::{IntersectEvent, IntersectList};
-use crate::material::{Dielectric, Lambertian, Metal};
-use crate::sphere::Sphere;
-use crate::vec::Vec3;
+use crate::film::{Filter, FilterType};
+use crate::instance::Instance;
+use crate::intersect::{AlphaMask, FlipNormals, IntersectEvent, IntersectList};
+use crate::light::{
+    DirectionalLight, Light, LightList, LightSurface, PointLight, SphereLight, SpotLight,
+};
+use crate::material::{
+    Coated, Conductor, Dielectric, DiffuseLight, Dispersion, HenyeyGreenstein, Isotropic,
+    Lambertian, MixMaterial, OrenNayar, Principled, Subsurface,
+};
+use crate::medium::{ConstantMedium, DensityGrid, Fog, GridMedium};
+use crate::noise::Perlin;
+use crate::plane::{Disk, Plane};
+use crate::rect::{Cuboid, XYRect, XZRect, YZRect};
+use crate::sampler::SamplerType;
+use crate::sky::{Preetham, Uniform};
+use crate::sphere::{MovingSphere, Sphere};
+use crate::texture::{GridTexture, NoiseTexture};
+use crate::vec::{AnimatedTransform, Keyframe, Quaternion, Transform, Vec3};
+use crate::IntegratorType;
 
 pub struct Params {
     pub nx: u32,
@@ -13,6 +33,17 @@ pub struct Params {
     pub ns: u32,
     pub random_seed: u64,
     pub output: String,
+    pub sampler: SamplerType,
+    pub integrator: IntegratorType,
+    pub filter: Filter,
+    pub max_depth: u32,
+    pub rr_start_depth: u32,
+    pub scene: String,
+    pub grid: Option<String>,
+    pub spectral: bool,
+    pub sun_elevation: f32,
+    pub sun_azimuth: f32,
+    pub turbidity: f32,
 }
 
 impl Params {
@@ -23,12 +54,23 @@ impl Params {
             ns,
             random_seed,
             output,
+            sampler: SamplerType::Independent,
+            integrator: IntegratorType::Path,
+            filter: Filter::new(FilterType::Box, FilterType::Box.default_radius()),
+            max_depth: 50,
+            rr_start_depth: 3,
+            scene: String::from("custom"),
+            grid: None,
+            spectral: false,
+            sun_elevation: 30.0,
+            sun_azimuth: 45.0,
+            turbidity: 3.0,
         }
     }
 }
 
 #[allow(dead_code)]
-pub fn custom_scene(nx: u32, ny: u32) -> (IntersectList, Camera) {
+pub fn custom_scene(nx: u32, ny: u32) -> (IntersectList, LightList, Camera) {
     // scene used in the README
     //
     let mut list: Vec<Box<dyn IntersectEvent>> = Vec::with_capacity(10);
@@ -36,62 +78,54 @@ pub fn custom_scene(nx: u32, ny: u32) -> (IntersectList, Camera) {
     // material options
     let ground = Lambertian::new(Vec3::new(0.35, 0.35, 0.45));
     let pink = Lambertian::new(Vec3::new(0.8, 0.4, 0.4));
-    let gold = Metal::new(0.0, Vec3::new(1.0, 0.8, 0.4));
-    let gold_rough = Metal::new(0.25, Vec3::new(1.0, 0.8, 0.4));
-    let silver = Metal::new(0.0, Vec3::new(0.8, 0.8, 0.8));
-    let silver_rough = Metal::new(0.25, Vec3::new(0.8, 0.8, 0.8));
+    let gold = Conductor::gold(0.0);
+    let gold_rough = Conductor::gold(0.5);
+    let silver = Conductor::silver(0.0);
+    let silver_rough = Conductor::silver(0.5);
     let glass = Dielectric::new(1.5, 0.0, Vec3::new(0.8, 0.8, 0.8));
-    let glass_rough = Dielectric::new(1.5, 0.15, Vec3::new(0.8, 0.8, 0.8));
+    let glass_rough = Dielectric::new(1.5, 0.3, Vec3::new(0.8, 0.8, 0.8));
 
     // create base
-    list.push(Box::new(Sphere::new(
-        Vec3::new(0.0, -1000.0, 0.0),
-        1000.0,
-        ground.clone(),
+    list.push(Box::new(Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        ground,
     )));
 
     // create large spheres
-    list.push(Box::new(Sphere::new(
-        Vec3::new(4.0, 0.5, 1.0),
-        0.5,
-        pink.clone(),
-    )));
+    list.push(Box::new(Sphere::new(Vec3::new(4.0, 0.5, 1.0), 0.5, pink)));
     list.push(Box::new(Sphere::new(
         Vec3::new(3.0, 0.5, 0.25),
         0.5,
-        silver.clone(),
-    )));
-    list.push(Box::new(Sphere::new(
-        Vec3::new(2.0, 0.5, -0.5),
-        0.5,
-        glass.clone(),
+        silver,
     )));
+    list.push(Box::new(Sphere::new(Vec3::new(2.0, 0.5, -0.5), 0.5, glass)));
     list.push(Box::new(Sphere::new(
         Vec3::new(4.0, 0.35, -1.15),
         0.35,
-        gold.clone(),
+        gold,
     )));
 
     // create small spheres
     list.push(Box::new(Sphere::new(
         Vec3::new(5.0, 0.20, -0.8),
         0.20,
-        glass_rough.clone(),
+        glass_rough,
     )));
     list.push(Box::new(Sphere::new(
         Vec3::new(4.2, 0.20, -0.6),
         0.20,
-        glass_rough.clone(),
+        glass_rough,
     )));
     list.push(Box::new(Sphere::new(
         Vec3::new(5.4, 0.20, 0.55),
         0.20,
-        gold_rough.clone(),
+        gold_rough,
     )));
     list.push(Box::new(Sphere::new(
         Vec3::new(5.0, 0.20, 0.25),
         0.20,
-        silver_rough.clone(),
+        silver_rough,
     )));
 
     // camera options
@@ -105,22 +139,19 @@ pub fn custom_scene(nx: u32, ny: u32) -> (IntersectList, Camera) {
         Vec3::new(5.5, 1.0, 0.0).length(),
     );
 
-    (IntersectList::new(list), cam)
+    (IntersectList::new(list), LightList::new(Vec::new()), cam)
 }
 
 #[allow(dead_code)]
-pub fn rtiow_scene(nx: u32, ny: u32, rng: &mut rand_pcg::Pcg64) -> (IntersectList, Camera) {
+pub fn rtiow_scene(
+    nx: u32,
+    ny: u32,
+    rng: &mut rand_pcg::Pcg64,
+) -> (IntersectList, LightList, Camera) {
     // scene used in `Ray Tracing in One Weekend`
     //
     let mut list: Vec<Box<dyn IntersectEvent>> = Vec::with_capacity(500);
 
-    // create base
-    list.push(Box::new(Sphere::new(
-        Vec3::new(0.0, -1000.0, 0.0),
-        1000.0,
-        Lambertian::new(Vec3::new(0.5, 0.5, 0.5)),
-    )));
-
     // create random small spheres
     for x in -11..11 {
         for z in -11..11 {
@@ -144,13 +175,13 @@ pub fn rtiow_scene(nx: u32, ny: u32, rng: &mut rand_pcg::Pcg64) -> (IntersectLis
                 list.push(Box::new(Sphere::new(
                     center,
                     0.2,
-                    Metal::new(
-                        0.5 * rng.gen::<f32>(),
+                    Conductor::from_reflectance(
                         Vec3::new(
                             0.5 * (1.0 + rng.gen::<f32>()),
                             0.5 * (1.0 + rng.gen::<f32>()),
                             0.5 * (1.0 + rng.gen::<f32>()),
                         ),
+                        0.7 * rng.gen::<f32>(),
                     ),
                 )));
             } else {
@@ -177,7 +208,7 @@ pub fn rtiow_scene(nx: u32, ny: u32, rng: &mut rand_pcg::Pcg64) -> (IntersectLis
     list.push(Box::new(Sphere::new(
         Vec3::new(4.0, 1.0, 0.0),
         1.0,
-        Metal::new(0.0, Vec3::new(0.6, 0.6, 0.6)),
+        Conductor::from_reflectance(Vec3::new(0.6, 0.6, 0.6), 0.0),
     )));
 
     // camera options
@@ -191,5 +222,1077 @@ pub fn rtiow_scene(nx: u32, ny: u32, rng: &mut rand_pcg::Pcg64) -> (IntersectLis
         10.0,
     );
 
-    (IntersectList::new(list), cam)
+    // create base; the infinite plane cannot be bounded so it is kept
+    // outside of the bvh
+    let base = Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Lambertian::new(Vec3::new(0.5, 0.5, 0.5)),
+    );
+
+    // create ground fog thinning out with altitude for depth cues; like the
+    // base it fills the whole scene
+    let fog = Fog::height(
+        0.025,
+        0.8,
+        0.0,
+        100.0,
+        HenyeyGreenstein::new(Vec3::new(0.95, 0.95, 0.95), 0.3),
+    );
+    let world: Vec<Box<dyn IntersectEvent>> = vec![
+        Box::new(base),
+        Box::new(fog),
+        Box::new(Bvh::new(list, 0.0, 0.0)),
+    ];
+
+    (IntersectList::new(world), LightList::new(Vec::new()), cam)
+}
+
+pub fn motion_scene(
+    nx: u32,
+    ny: u32,
+    rng: &mut rand_pcg::Pcg64,
+) -> (IntersectList, LightList, Camera) {
+    // variant of `rtiow_scene` with bouncing diffuse spheres and a spinning
+    // box, rendered with the shutter open over [0, 1]
+    //
+    let mut list: Vec<Box<dyn IntersectEvent>> = Vec::with_capacity(150);
+
+    // create random small spheres; diffuse spheres move upwards while the
+    // shutter is open
+    for x in -5..5 {
+        for z in -5..5 {
+            let center = Vec3::new(
+                x as f32 + 0.9 * rng.gen::<f32>(),
+                0.2,
+                z as f32 + 0.9 * rng.gen::<f32>(),
+            );
+            if rng.gen::<f32>() < 0.8 {
+                let bounce = Vec3::new(0.0, 0.5 * rng.gen::<f32>(), 0.0);
+                list.push(Box::new(MovingSphere::new(
+                    vec![(0.0, center), (1.0, center + bounce)],
+                    0.2,
+                    Lambertian::new(Vec3::new(
+                        rng.gen::<f32>() * rng.gen::<f32>(),
+                        rng.gen::<f32>() * rng.gen::<f32>(),
+                        rng.gen::<f32>() * rng.gen::<f32>(),
+                    )),
+                )));
+            } else {
+                list.push(Box::new(Sphere::new(center, 0.2, Conductor::copper(0.3))));
+            }
+        }
+    }
+
+    // create spinning box, rotating a quarter turn about the y-axis
+    let cube: Rc<dyn IntersectEvent> = Rc::new(Cuboid::new(
+        Vec3::new(-0.5, -0.5, -0.5),
+        Vec3::new(0.5, 0.5, 0.5),
+        Lambertian::new(Vec3::new(0.4, 0.2, 0.1)),
+    ));
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    let scale = Vec3::new(1.5, 1.5, 1.5);
+    let motion = AnimatedTransform::new(vec![
+        Keyframe::new(
+            0.0,
+            Vec3::new(0.0, 1.0, -2.8),
+            Quaternion::from_axis_angle(up, 0.0),
+            scale,
+        ),
+        Keyframe::new(
+            1.0,
+            Vec3::new(0.0, 1.0, -2.8),
+            Quaternion::from_axis_angle(up, 90.0),
+            scale,
+        ),
+    ]);
+    list.push(Box::new(Instance::animated(
+        cube,
+        Transform::rotate(Vec3::new(1.0, 0.0, 1.0), 30.0),
+        motion,
+    )));
+
+    // create large spheres
+    list.push(Box::new(MovingSphere::new(
+        vec![
+            (0.0, Vec3::new(1.0, 1.0, -0.5)),
+            (0.5, Vec3::new(1.0, 1.5, 0.0)),
+            (1.0, Vec3::new(1.0, 1.0, 0.5)),
+        ],
+        1.0,
+        Lambertian::new(Vec3::new(0.8, 0.4, 0.4)),
+    )));
+    list.push(Box::new(Sphere::new(
+        Vec3::new(4.0, 1.0, 0.0),
+        1.0,
+        Conductor::aluminium(0.0).with_anisotropy(0.1, 0.5),
+    )));
+
+    // camera options
+    let cam = Camera::new(
+        Vec3::new(12.0, 2.0, 2.0),
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        25.0,
+        nx as f32 / ny as f32,
+        0.0,
+        10.0,
+    )
+    .with_shutter(0.0, 1.0);
+
+    // create base
+    let base = Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Lambertian::new(Vec3::new(0.5, 0.5, 0.5)),
+    );
+
+    // create thin uniform haze
+    let haze = Fog::homogeneous(0.005, 30.0, Isotropic::new(Vec3::new(0.9, 0.9, 0.9)));
+    let world: Vec<Box<dyn IntersectEvent>> = vec![
+        Box::new(base),
+        Box::new(haze),
+        Box::new(Bvh::new(list, cam.time_open, cam.time_close)),
+    ];
+
+    (IntersectList::new(world), LightList::new(Vec::new()), cam)
+}
+
+fn cornell_walls() -> Vec<Box<dyn IntersectEvent>> {
+    // walls of a cornell box spanning [0, 555] on every axis, open towards -z,
+    // with normals facing into the box and an opening in the ceiling
+    let mut list: Vec<Box<dyn IntersectEvent>> = Vec::with_capacity(20);
+
+    // material options
+    let red = Lambertian::new(Vec3::new(0.65, 0.05, 0.05));
+    let white = Lambertian::new(Vec3::new(0.73, 0.73, 0.73));
+    let green = Lambertian::new(Vec3::new(0.12, 0.45, 0.15));
+
+    // create walls
+    list.push(Box::new(FlipNormals::new(YZRect::new(
+        0.0, 555.0, 0.0, 555.0, 555.0, green,
+    ))));
+    list.push(Box::new(YZRect::new(0.0, 555.0, 0.0, 555.0, 0.0, red)));
+    list.push(Box::new(XZRect::new(0.0, 555.0, 0.0, 555.0, 0.0, white)));
+    list.push(Box::new(FlipNormals::new(XYRect::new(
+        0.0, 555.0, 0.0, 555.0, 555.0, white,
+    ))));
+
+    // create ceiling around the opening [213, 343] x [227, 332]
+    list.push(Box::new(FlipNormals::new(XZRect::new(
+        0.0, 213.0, 0.0, 555.0, 555.0, white,
+    ))));
+    list.push(Box::new(FlipNormals::new(XZRect::new(
+        343.0, 555.0, 0.0, 555.0, 555.0, white,
+    ))));
+    list.push(Box::new(FlipNormals::new(XZRect::new(
+        213.0, 343.0, 0.0, 227.0, 555.0, white,
+    ))));
+    list.push(Box::new(FlipNormals::new(XZRect::new(
+        213.0, 343.0, 332.0, 555.0, 555.0, white,
+    ))));
+
+    list
+}
+
+fn cornell_camera(nx: u32, ny: u32) -> Camera {
+    Camera::new(
+        Vec3::new(278.0, 278.0, -800.0),
+        Vec3::new(278.0, 278.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        40.0,
+        nx as f32 / ny as f32,
+        0.0,
+        10.0,
+    )
+}
+
+pub fn cornell_scene(nx: u32, ny: u32) -> (IntersectList, LightList, Camera) {
+    // cornell box lit by the sky through an opening in the ceiling
+    //
+    let mut list = cornell_walls();
+
+    // material options
+    let white = Lambertian::new(Vec3::new(0.73, 0.73, 0.73));
+    let mirror = Conductor::silver(0.0);
+
+    // create mirror on the back wall
+    list.push(Box::new(Disk::new(
+        Vec3::new(400.0, 380.0, 554.0),
+        Vec3::new(0.0, 0.0, -1.0),
+        80.0,
+        mirror,
+    )));
+
+    // create boxes as rotated instances of a shared unit cube
+    let cube: Rc<dyn IntersectEvent> = Rc::new(Cuboid::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(1.0, 1.0, 1.0),
+        white,
+    ));
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    list.push(Box::new(Instance::new(
+        cube.clone(),
+        Transform::translate(Vec3::new(130.0, 0.0, 65.0))
+            * Transform::rotate(up, -18.0)
+            * Transform::scale(Vec3::new(165.0, 165.0, 165.0)),
+    )));
+    list.push(Box::new(Instance::new(
+        cube,
+        Transform::translate(Vec3::new(265.0, 0.0, 295.0))
+            * Transform::rotate(up, 15.0)
+            * Transform::scale(Vec3::new(165.0, 330.0, 165.0)),
+    )));
+
+    (
+        IntersectList::new(list),
+        LightList::new(Vec::new()),
+        cornell_camera(nx, ny),
+    )
+}
+
+pub fn volume_scene(nx: u32, ny: u32) -> (IntersectList, LightList, Camera) {
+    // cornell box filled with a thin haze, containing boxes of smoke and a
+    // glass sphere of milk
+    //
+    let mut list = cornell_walls();
+
+    // create boxes of dark and light smoke
+    let cube: Rc<dyn IntersectEvent> = Rc::new(Cuboid::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(1.0, 1.0, 1.0),
+        Lambertian::new(Vec3::new(0.73, 0.73, 0.73)),
+    ));
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    list.push(Box::new(ConstantMedium::new(
+        Instance::new(
+            cube.clone(),
+            Transform::translate(Vec3::new(130.0, 0.0, 65.0))
+                * Transform::rotate(up, -18.0)
+                * Transform::scale(Vec3::new(165.0, 165.0, 165.0)),
+        ),
+        0.01,
+        Isotropic::new(Vec3::new(0.9, 0.9, 0.9)),
+    )));
+    list.push(Box::new(ConstantMedium::new(
+        Instance::new(
+            cube,
+            Transform::translate(Vec3::new(265.0, 0.0, 295.0))
+                * Transform::rotate(up, 15.0)
+                * Transform::scale(Vec3::new(165.0, 330.0, 165.0)),
+        ),
+        0.01,
+        Isotropic::new(Vec3::new(0.2, 0.2, 0.2)),
+    )));
+
+    // create milk sphere: a dense medium inside a glass shell
+    let center = Vec3::new(430.0, 80.0, 120.0);
+    list.push(Box::new(Sphere::new(
+        center,
+        80.0,
+        Dielectric::new(1.5, 0.0, Vec3::new(1.0, 1.0, 1.0)),
+    )));
+    list.push(Box::new(ConstantMedium::new(
+        Sphere::new(center, 80.0, Lambertian::new(Vec3::new(1.0, 1.0, 1.0))),
+        0.2,
+        Isotropic::new(Vec3::new(0.95, 0.95, 0.92)),
+    )));
+
+    // fill the box with haze
+    list.push(Box::new(ConstantMedium::new(
+        Cuboid::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(555.0, 555.0, 555.0),
+            Lambertian::new(Vec3::new(1.0, 1.0, 1.0)),
+        ),
+        0.0005,
+        Isotropic::new(Vec3::new(1.0, 1.0, 1.0)),
+    )));
+
+    (
+        IntersectList::new(list),
+        LightList::new(Vec::new()),
+        cornell_camera(nx, ny),
+    )
+}
+
+pub fn cloud_scene(
+    nx: u32,
+    ny: u32,
+    grid: Option<&str>,
+    rng: &mut rand_pcg::Pcg64,
+) -> (IntersectList, LightList, Camera) {
+    // cloud above a ground plane; the cloud densities are read from the grid
+    // file `grid` if given and generated from noise otherwise
+    //
+    let mut list: Vec<Box<dyn IntersectEvent>> = Vec::with_capacity(2);
+
+    // create ground
+    list.push(Box::new(Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Lambertian::new(Vec3::new(0.4, 0.45, 0.35)),
+    )));
+
+    // create cloud with forward scattering phase function
+    let grid = match grid {
+        Some(path) => DensityGrid::load(path).expect("error loading density grid"),
+        None => DensityGrid::from_noise(64, &Perlin::new(rng), 4.0, 5),
+    };
+    list.push(Box::new(GridMedium::new(
+        Aabb::new(Vec3::new(-2.5, 0.5, -2.5), Vec3::new(2.5, 3.5, 2.5)),
+        grid,
+        4.0,
+        HenyeyGreenstein::new(Vec3::new(0.95, 0.95, 0.95), 0.6),
+    )));
+
+    // create camera
+    let look_from = Vec3::new(0.0, 2.0, 9.0);
+    let look_to = Vec3::new(0.0, 2.0, 0.0);
+    let cam = Camera::new(
+        look_from,
+        look_to,
+        Vec3::new(0.0, 1.0, 0.0),
+        45.0,
+        nx as f32 / ny as f32,
+        0.0,
+        9.0,
+    );
+
+    (IntersectList::new(list), LightList::new(Vec::new()), cam)
+}
+
+pub fn principled_scene(nx: u32, ny: u32) -> (IntersectList, LightList, Camera) {
+    // row of spheres showing the parameters of the principled material
+    //
+    let mut list: Vec<Box<dyn IntersectEvent>> = Vec::with_capacity(8);
+
+    // material options
+    let ground = Principled::new(Vec3::new(0.5, 0.5, 0.5)).with_roughness(0.9);
+    let car_paint = Principled::new(Vec3::new(0.6, 0.05, 0.05))
+        .with_roughness(0.4)
+        .with_clearcoat(1.0);
+    let brushed_gold = Principled::new(Vec3::new(1.0, 0.78, 0.34))
+        .with_metallic(1.0)
+        .with_roughness(0.3);
+    let glass = Principled::new(Vec3::new(1.0, 1.0, 1.0))
+        .with_transmission(1.0)
+        .with_roughness(0.05);
+    let velvet = Principled::new(Vec3::new(0.2, 0.05, 0.4))
+        .with_roughness(0.8)
+        .with_specular(0.2)
+        .with_sheen(1.0);
+    let plastic = Principled::new(Vec3::new(0.1, 0.3, 0.6))
+        .with_roughness(0.15)
+        .with_specular(1.0);
+    let lamp =
+        Principled::new(Vec3::new(0.0, 0.0, 0.0)).with_emission(Vec3::new(1.0, 0.6, 0.3), 4.0);
+
+    // create base
+    list.push(Box::new(Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        ground,
+    )));
+
+    // create spheres
+    let materials = [car_paint, brushed_gold, glass, velvet, plastic];
+    for (i, material) in materials.iter().enumerate() {
+        list.push(Box::new(Sphere::new(
+            Vec3::new(0.0, 0.5, 2.4 - 1.2 * i as f32),
+            0.5,
+            *material,
+        )));
+    }
+    list.push(Box::new(Sphere::new(Vec3::new(1.0, 0.2, -0.6), 0.2, lamp)));
+
+    // camera options
+    let cam = Camera::new(
+        Vec3::new(8.0, 1.5, 0.0),
+        Vec3::new(0.0, 0.5, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        30.0,
+        nx as f32 / ny as f32,
+        0.0,
+        8.0,
+    );
+
+    (IntersectList::new(list), LightList::new(Vec::new()), cam)
+}
+
+pub fn diffuse_scene(nx: u32, ny: u32) -> (IntersectList, LightList, Camera) {
+    // pairs of smooth (lambertian) and rough (oren-nayar) diffuse spheres
+    // of clay, concrete and cloth on a concrete floor; rough spheres appear
+    // flatter, without darkening towards their silhouettes
+    //
+    let mut list: Vec<Box<dyn IntersectEvent>> = Vec::with_capacity(8);
+
+    // material options
+    let ground = OrenNayar::new(Vec3::new(0.5, 0.5, 0.5), 30.0);
+    let colors = [
+        (Vec3::new(0.6, 0.3, 0.2), 20.0),
+        (Vec3::new(0.55, 0.55, 0.5), 35.0),
+        (Vec3::new(0.2, 0.3, 0.6), 60.0),
+    ];
+
+    // create base
+    list.push(Box::new(Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        ground,
+    )));
+
+    // create spheres; lambertian in the front row and oren-nayar behind
+    for (i, (albedo, sigma)) in colors.iter().enumerate() {
+        let x = 1.2 * (i as f32 - 1.0);
+        list.push(Box::new(Sphere::new(
+            Vec3::new(x, 0.5, 0.7),
+            0.5,
+            Lambertian::new(*albedo),
+        )));
+        list.push(Box::new(Sphere::new(
+            Vec3::new(x, 0.5, -0.7),
+            0.5,
+            OrenNayar::new(*albedo, *sigma),
+        )));
+    }
+
+    // camera options
+    let cam = Camera::new(
+        Vec3::new(0.0, 3.0, 6.0),
+        Vec3::new(0.0, 0.4, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        35.0,
+        nx as f32 / ny as f32,
+        0.0,
+        6.0,
+    );
+
+    (IntersectList::new(list), LightList::new(Vec::new()), cam)
+}
+
+pub fn coated_scene(nx: u32, ny: u32) -> (IntersectList, LightList, Camera) {
+    // layered materials with a dielectric coating over a base: car paint,
+    // varnished wood, lacquered copper and a thick tinted coat over a rough
+    // white base, on a varnished floor
+    //
+    let mut list: Vec<Box<dyn IntersectEvent>> = Vec::with_capacity(8);
+
+    // material options
+    let floor = Coated::new(OrenNayar::new(Vec3::new(0.35, 0.2, 0.1), 20.0), 1.5, 0.2)
+        .with_absorption(Vec3::new(0.9, 0.7, 0.4), 0.5);
+    let car_paint = Coated::new(Lambertian::new(Vec3::new(0.6, 0.02, 0.02)), 1.5, 0.0);
+    let wood = Coated::new(OrenNayar::new(Vec3::new(0.5, 0.3, 0.15), 20.0), 1.5, 0.05)
+        .with_absorption(Vec3::new(0.9, 0.7, 0.4), 1.0);
+    let lacquer = Coated::new(Conductor::copper(0.3), 1.5, 0.0);
+    let enamel = Coated::new(OrenNayar::new(Vec3::new(0.9, 0.9, 0.9), 30.0), 1.5, 0.3)
+        .with_absorption(Vec3::new(0.2, 0.5, 0.9), 1.0);
+
+    // create base
+    list.push(Box::new(Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        floor,
+    )));
+
+    // create spheres
+    list.push(Box::new(Sphere::new(
+        Vec3::new(-1.8, 0.5, 0.0),
+        0.5,
+        car_paint,
+    )));
+    list.push(Box::new(Sphere::new(Vec3::new(-0.6, 0.5, 0.0), 0.5, wood)));
+    list.push(Box::new(Sphere::new(
+        Vec3::new(0.6, 0.5, 0.0),
+        0.5,
+        lacquer,
+    )));
+    list.push(Box::new(Sphere::new(Vec3::new(1.8, 0.5, 0.0), 0.5, enamel)));
+
+    // camera options
+    let cam = Camera::new(
+        Vec3::new(0.0, 2.0, 6.0),
+        Vec3::new(0.0, 0.5, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        35.0,
+        nx as f32 / ny as f32,
+        0.0,
+        6.0,
+    );
+
+    (IntersectList::new(list), LightList::new(Vec::new()), cam)
+}
+
+pub fn mix_scene(
+    nx: u32,
+    ny: u32,
+    rng: &mut rand_pcg::Pcg64,
+) -> (IntersectList, LightList, Camera) {
+    // blended materials, rusty steel and dirt over paint, behind a wire fence
+    // and next to a bush cut from a sphere by alpha masks
+    //
+    let mut list: Vec<Box<dyn IntersectEvent>> = Vec::with_capacity(8);
+
+    // material options
+    let ground = Lambertian::new(Vec3::new(0.5, 0.5, 0.5));
+    let steel = Conductor::from_reflectance(Vec3::new(0.55, 0.55, 0.55), 0.3);
+    let rust = OrenNayar::new(Vec3::new(0.45, 0.18, 0.07), 30.0);
+    let rusty_steel = MixMaterial::new(
+        steel,
+        rust,
+        NoiseTexture::new(Perlin::new(rng), 3.0, 5).with_threshold(0.55, 0.1),
+    );
+    let paint = Coated::new(Lambertian::new(Vec3::new(0.1, 0.3, 0.6)), 1.5, 0.0);
+    let dirt = OrenNayar::new(Vec3::new(0.3, 0.22, 0.15), 40.0);
+    let dirty_paint = MixMaterial::new(
+        paint,
+        dirt,
+        NoiseTexture::new(Perlin::new(rng), 2.0, 6).with_threshold(0.6, 0.05),
+    );
+    let wire = Conductor::aluminium(0.4);
+    let leaves = Lambertian::new(Vec3::new(0.15, 0.4, 0.1));
+
+    // create base
+    list.push(Box::new(Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        ground,
+    )));
+
+    // create spheres
+    list.push(Box::new(Sphere::new(
+        Vec3::new(-1.2, 0.6, 0.0),
+        0.6,
+        rusty_steel,
+    )));
+    list.push(Box::new(Sphere::new(
+        Vec3::new(0.2, 0.6, -0.4),
+        0.6,
+        dirty_paint,
+    )));
+
+    // create bush of scattered leaves
+    list.push(Box::new(AlphaMask::new(
+        Sphere::new(Vec3::new(1.6, 0.7, -0.2), 0.7, leaves),
+        NoiseTexture::new(Perlin::new(rng), 8.0, 3).with_threshold(0.5, 0.01),
+    )));
+
+    // create wire fence in front of the spheres
+    list.push(Box::new(AlphaMask::new(
+        XYRect::new(-3.0, 3.0, 0.0, 1.0, 1.2, wire),
+        GridTexture::new(48.0, 8.0, 0.12),
+    )));
+
+    // camera options
+    let cam = Camera::new(
+        Vec3::new(0.5, 1.8, 5.5),
+        Vec3::new(0.2, 0.5, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        35.0,
+        nx as f32 / ny as f32,
+        0.0,
+        4.3,
+    );
+
+    (IntersectList::new(list), LightList::new(Vec::new()), cam)
+}
+
+pub fn subsurface_scene(nx: u32, ny: u32) -> (IntersectList, LightList, Camera) {
+    // translucent skin, wax and marble lit from behind by a lamp, showing
+    // light scattered through the thin parts of the objects
+    //
+    let mut list: Vec<Box<dyn IntersectEvent>> = Vec::with_capacity(8);
+
+    // material options
+    let ground = Lambertian::new(Vec3::new(0.5, 0.5, 0.5));
+    let skin = Subsurface::new(
+        Vec3::new(0.85, 0.55, 0.4),
+        Vec3::new(0.12, 0.05, 0.03),
+        1.4,
+        0.3,
+    );
+    let wax = Subsurface::new(
+        Vec3::new(0.95, 0.8, 0.5),
+        Vec3::new(0.1, 0.08, 0.05),
+        1.45,
+        0.1,
+    )
+    .with_anisotropy(0.3);
+    let marble = Subsurface::new(
+        Vec3::new(0.93, 0.93, 0.9),
+        Vec3::new(0.08, 0.08, 0.08),
+        1.5,
+        0.0,
+    );
+    let lamp =
+        Principled::new(Vec3::new(0.0, 0.0, 0.0)).with_emission(Vec3::new(1.0, 0.9, 0.8), 8.0);
+
+    // create base
+    list.push(Box::new(Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        ground,
+    )));
+
+    // create translucent objects
+    list.push(Box::new(Sphere::new(Vec3::new(-1.2, 0.5, 0.0), 0.5, skin)));
+    list.push(Box::new(Sphere::new(Vec3::new(0.0, 0.5, 0.0), 0.5, wax)));
+    list.push(Box::new(Cuboid::new(
+        Vec3::new(0.8, 0.0, -0.4),
+        Vec3::new(1.6, 0.8, 0.4),
+        marble,
+    )));
+
+    // create lamp behind the objects
+    list.push(Box::new(Sphere::new(Vec3::new(0.0, 1.5, -2.5), 0.5, lamp)));
+
+    // camera options
+    let cam = Camera::new(
+        Vec3::new(0.0, 1.5, 5.0),
+        Vec3::new(0.0, 0.5, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        35.0,
+        nx as f32 / ny as f32,
+        0.0,
+        5.0,
+    );
+
+    (IntersectList::new(list), LightList::new(Vec::new()), cam)
+}
+
+pub fn lights_scene(nx: u32, ny: u32) -> (IntersectList, LightList, Camera) {
+    // courtyard lit by the sun with soft-edged shadows; the spheres in the
+    // shadow of the wall are lit by a point light and a spot light
+    //
+    let mut list: Vec<Box<dyn IntersectEvent>> = Vec::with_capacity(8);
+
+    // material options
+    let ground = Lambertian::new(Vec3::new(0.5, 0.5, 0.5));
+    let wall = OrenNayar::new(Vec3::new(0.7, 0.65, 0.6), 20.0);
+    let clay = OrenNayar::new(Vec3::new(0.6, 0.3, 0.2), 20.0);
+    let plastic = Principled::new(Vec3::new(0.1, 0.3, 0.6)).with_roughness(0.3);
+    let gold = Conductor::gold(0.3);
+    let paint = Coated::new(Lambertian::new(Vec3::new(0.6, 0.05, 0.05)), 1.5, 0.1);
+
+    // create base and wall
+    list.push(Box::new(Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        ground,
+    )));
+    list.push(Box::new(Cuboid::new(
+        Vec3::new(-4.0, 0.0, -1.5),
+        Vec3::new(4.0, 2.0, -1.2),
+        wall,
+    )));
+
+    // create spheres in the shadow of the wall and in the sun
+    list.push(Box::new(Sphere::new(Vec3::new(-1.5, 0.5, 0.0), 0.5, clay)));
+    list.push(Box::new(Sphere::new(
+        Vec3::new(0.0, 0.5, 0.0),
+        0.5,
+        plastic,
+    )));
+    list.push(Box::new(Sphere::new(Vec3::new(1.5, 0.5, 0.0), 0.5, gold)));
+    list.push(Box::new(Sphere::new(Vec3::new(0.5, 0.5, 2.2), 0.5, paint)));
+
+    // lights
+    let lights: Vec<Box<dyn Light>> = vec![
+        Box::new(DirectionalLight::new(
+            Vec3::new(0.3, -1.0, 1.2),
+            Vec3::new(3.0, 2.8, 2.5),
+            0.53,
+        )),
+        Box::new(PointLight::new(
+            Vec3::new(-1.0, 1.5, 1.0),
+            Vec3::new(2.0, 1.5, 1.0),
+        )),
+        Box::new(SpotLight::new(
+            Vec3::new(1.8, 2.5, 1.5),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(6.0, 6.0, 8.0),
+            40.0,
+            0.3,
+        )),
+    ];
+
+    // camera options
+    let cam = Camera::new(
+        Vec3::new(0.0, 2.0, 7.0),
+        Vec3::new(0.0, 0.5, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        40.0,
+        nx as f32 / ny as f32,
+        0.0,
+        7.0,
+    );
+
+    (IntersectList::new(list), LightList::new(lights), cam)
+}
+
+pub fn sky_scene(
+    nx: u32,
+    ny: u32,
+    elevation: f32,
+    azimuth: f32,
+    turbidity: f32,
+) -> (IntersectList, LightList, Camera) {
+    // outdoor scene under a daylight sky with the sun at `elevation` and
+    // `azimuth` (in degrees) through an atmosphere of `turbidity`
+    //
+    let mut list: Vec<Box<dyn IntersectEvent>> = Vec::with_capacity(8);
+
+    // material options
+    let ground_albedo = Vec3::new(0.3, 0.3, 0.3);
+    let ground = Lambertian::new(ground_albedo);
+    let stone = OrenNayar::new(Vec3::new(0.7, 0.65, 0.6), 20.0);
+    let paint = Coated::new(Lambertian::new(Vec3::new(0.6, 0.05, 0.05)), 1.5, 0.1);
+    let chrome = Conductor::silver(0.05);
+
+    // create base
+    list.push(Box::new(Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        ground,
+    )));
+
+    // create pillars and spheres
+    for i in 0..4 {
+        let z = -3.0 * i as f32;
+        list.push(Box::new(Cuboid::new(
+            Vec3::new(-2.5, 0.0, z - 0.3),
+            Vec3::new(-1.9, 3.0, z + 0.3),
+            stone,
+        )));
+    }
+    list.push(Box::new(Sphere::new(Vec3::new(0.0, 0.6, 0.0), 0.6, paint)));
+    list.push(Box::new(Sphere::new(
+        Vec3::new(1.5, 0.6, -1.5),
+        0.6,
+        chrome,
+    )));
+
+    // lights
+    let sky = Preetham::new(elevation, azimuth, turbidity, ground_albedo);
+    let mut lights: Vec<Box<dyn Light>> = Vec::new();
+    if let Some(sun) = sky.sun() {
+        lights.push(Box::new(sun));
+    }
+
+    // camera options
+    let cam = Camera::new(
+        Vec3::new(1.0, 1.5, 6.0),
+        Vec3::new(0.0, 1.2, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        50.0,
+        nx as f32 / ny as f32,
+        0.0,
+        6.0,
+    );
+
+    (
+        IntersectList::new(list),
+        LightList::new(lights).with_environment(sky),
+        cam,
+    )
+}
+
+pub fn emissive_scene(
+    nx: u32,
+    ny: u32,
+    rng: &mut rand_pcg::Pcg64,
+) -> (IntersectList, LightList, Camera) {
+    // night-time variant of `rtiow_scene` lit only by hundreds of small
+    // glowing spheres, which are sampled through the light bvh
+    //
+    let mut list: Vec<Box<dyn IntersectEvent>> = Vec::with_capacity(500);
+    let mut lights: Vec<Box<dyn Light>> = Vec::with_capacity(500);
+
+    // create random small spheres; each glowing sphere is tagged with the
+    // light sampling it
+    for x in -11..11 {
+        for z in -11..11 {
+            let center = Vec3::new(
+                x as f32 + 0.9 * rng.gen::<f32>(),
+                0.2,
+                z as f32 + 0.9 * rng.gen::<f32>(),
+            );
+            let material_prob = rng.gen::<f32>();
+            if material_prob < 0.5 {
+                let emission = Vec3::new(
+                    0.2 + 0.8 * rng.gen::<f32>(),
+                    0.2 + 0.8 * rng.gen::<f32>(),
+                    0.2 + 0.8 * rng.gen::<f32>(),
+                ) * 4.0;
+                let sphere = Sphere::new(center, 0.2, DiffuseLight::new(emission));
+                list.push(Box::new(LightSurface::new(sphere, lights.len())));
+                lights.push(Box::new(SphereLight::new(center, 0.2, emission)));
+            } else if material_prob < 0.8 {
+                list.push(Box::new(Sphere::new(
+                    center,
+                    0.2,
+                    Lambertian::new(Vec3::new(
+                        rng.gen::<f32>() * rng.gen::<f32>(),
+                        rng.gen::<f32>() * rng.gen::<f32>(),
+                        rng.gen::<f32>() * rng.gen::<f32>(),
+                    )),
+                )));
+            } else {
+                list.push(Box::new(Sphere::new(
+                    center,
+                    0.2,
+                    Conductor::from_reflectance(
+                        Vec3::new(
+                            0.5 * (1.0 + rng.gen::<f32>()),
+                            0.5 * (1.0 + rng.gen::<f32>()),
+                            0.5 * (1.0 + rng.gen::<f32>()),
+                        ),
+                        0.7 * rng.gen::<f32>(),
+                    ),
+                )));
+            }
+        }
+    }
+
+    // create large spheres
+    list.push(Box::new(Sphere::new(
+        Vec3::new(0.0, 1.0, 0.0),
+        1.0,
+        Dielectric::new(1.5, 0.0, Vec3::new(0.8, 0.8, 0.8)),
+    )));
+    list.push(Box::new(Sphere::new(
+        Vec3::new(-4.0, 1.0, 0.0),
+        1.0,
+        Lambertian::new(Vec3::new(0.4, 0.2, 0.1)),
+    )));
+    list.push(Box::new(Sphere::new(
+        Vec3::new(4.0, 1.0, 0.0),
+        1.0,
+        Conductor::from_reflectance(Vec3::new(0.6, 0.6, 0.6), 0.0),
+    )));
+
+    // camera options
+    let cam = Camera::new(
+        Vec3::new(12.0, 2.0, 2.0),
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        20.0,
+        nx as f32 / ny as f32,
+        0.1,
+        10.0,
+    );
+
+    // create base; the infinite plane cannot be bounded so it is kept
+    // outside of the bvh
+    let base = Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Lambertian::new(Vec3::new(0.5, 0.5, 0.5)),
+    );
+    let world: Vec<Box<dyn IntersectEvent>> =
+        vec![Box::new(base), Box::new(Bvh::new(list, 0.0, 0.0))];
+
+    // dark night sky
+    let lights = LightList::new(lights).with_environment(Uniform::new(Vec3::new(0.01, 0.01, 0.02)));
+
+    (IntersectList::new(world), lights, cam)
+}
+
+pub fn glass_scene(nx: u32, ny: u32) -> (IntersectList, LightList, Camera) {
+    // blocks and panes of colored glass, where thick glass absorbs more light,
+    // next to nested dielectrics: a glass ball filled with liquid and an ice
+    // cube with trapped air bubbles
+    //
+    let mut list: Vec<Box<dyn IntersectEvent>> = Vec::with_capacity(8);
+
+    // material options
+    let ground = Lambertian::new(Vec3::new(0.8, 0.8, 0.8));
+    let green = Dielectric::new(1.5, 0.0, Vec3::new(1.0, 1.0, 1.0))
+        .with_absorption(Vec3::new(0.3, 0.8, 0.4), 2.0);
+    let blue = Dielectric::new(1.5, 0.0, Vec3::new(1.0, 1.0, 1.0))
+        .with_absorption(Vec3::new(0.3, 0.5, 0.9), 1.0);
+    let clear = Dielectric::new(1.5, 0.0, Vec3::new(1.0, 1.0, 1.0)).with_priority(1);
+    let liquid = Dielectric::new(1.33, 0.0, Vec3::new(1.0, 1.0, 1.0))
+        .with_absorption(Vec3::new(0.9, 0.5, 0.2), 2.0)
+        .with_priority(2);
+    let ice = Dielectric::new(1.31, 0.05, Vec3::new(1.0, 1.0, 1.0))
+        .with_absorption(Vec3::new(0.85, 0.95, 1.0), 1.0)
+        .with_priority(1);
+    let air = Dielectric::new(1.0, 0.0, Vec3::new(1.0, 1.0, 1.0)).with_priority(3);
+
+    // create base
+    list.push(Box::new(Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        ground,
+    )));
+
+    // create thick block and thin pane of the same glass
+    list.push(Box::new(Cuboid::new(
+        Vec3::new(-0.4, 0.0, 0.6),
+        Vec3::new(0.4, 0.8, 1.4),
+        green,
+    )));
+    list.push(Box::new(Cuboid::new(
+        Vec3::new(-0.02, 0.0, -0.4),
+        Vec3::new(0.02, 0.8, 0.4),
+        green,
+    )));
+
+    // create glass sphere
+    list.push(Box::new(Sphere::new(Vec3::new(0.0, 0.5, -1.2), 0.5, blue)));
+
+    // create glass ball filled with liquid holding an air bubble; the liquid
+    // overrides the glass so that only a shell of glass remains
+    let center = Vec3::new(0.0, 0.5, -2.4);
+    list.push(Box::new(Sphere::new(center, 0.5, clear)));
+    list.push(Box::new(Sphere::new(center, 0.45, liquid)));
+    list.push(Box::new(Sphere::new(
+        center + Vec3::new(0.2, 0.1, 0.1),
+        0.1,
+        air,
+    )));
+
+    // create ice cube with air bubbles
+    list.push(Box::new(Cuboid::new(
+        Vec3::new(-0.35, 0.0, 2.05),
+        Vec3::new(0.35, 0.7, 2.75),
+        ice,
+    )));
+    for (offset, radius) in [
+        (Vec3::new(0.1, 0.3, 2.3), 0.08),
+        (Vec3::new(-0.1, 0.45, 2.5), 0.06),
+        (Vec3::new(0.15, 0.2, 2.55), 0.05),
+    ] {
+        list.push(Box::new(Sphere::new(offset, radius, air)));
+    }
+
+    // camera options
+    let cam = Camera::new(
+        Vec3::new(5.0, 1.6, 0.0),
+        Vec3::new(0.0, 0.4, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        45.0,
+        nx as f32 / ny as f32,
+        0.0,
+        6.0,
+    );
+
+    (IntersectList::new(list), LightList::new(Vec::new()), cam)
+}
+
+pub fn dispersion_scene(nx: u32, ny: u32) -> (IntersectList, LightList, Camera) {
+    // dispersive diamond and flint glass spheres and a crown glass prism in
+    // front of a bright bar of light; render with `--spectral`
+    //
+    let mut list: Vec<Box<dyn IntersectEvent>> = Vec::with_capacity(8);
+
+    // material options
+    let ground = Lambertian::new(Vec3::new(0.3, 0.3, 0.3));
+    let clear = Vec3::new(1.0, 1.0, 1.0);
+    let diamond = Dielectric::new(2.4, 0.0, clear).with_dispersion(Dispersion::diamond());
+    let flint =
+        Dielectric::new(1.7, 0.0, clear).with_dispersion(Dispersion::cauchy(1.728, 0.01342));
+    let crown = Dielectric::new(1.5, 0.0, clear).with_dispersion(Dispersion::bk7());
+    let light =
+        Principled::new(Vec3::new(0.0, 0.0, 0.0)).with_emission(Vec3::new(1.0, 1.0, 1.0), 8.0);
+
+    // create base
+    list.push(Box::new(Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        ground,
+    )));
+
+    // create bar of light behind the objects
+    list.push(Box::new(XYRect::new(-4.0, 4.0, 1.3, 1.45, -3.0, light)));
+
+    // create spheres
+    list.push(Box::new(Sphere::new(
+        Vec3::new(-1.3, 0.6, 0.0),
+        0.6,
+        diamond,
+    )));
+    list.push(Box::new(Sphere::new(Vec3::new(0.0, 0.6, 0.0), 0.6, flint)));
+
+    // create prism: a cube standing on an edge
+    let cube: Rc<dyn IntersectEvent> = Rc::new(Cuboid::new(
+        Vec3::new(-0.5, -0.5, -0.5),
+        Vec3::new(0.5, 0.5, 0.5),
+        crown,
+    ));
+    list.push(Box::new(Instance::new(
+        cube,
+        Transform::translate(Vec3::new(1.4, 0.71, 0.0))
+            * Transform::rotate(Vec3::new(0.0, 0.0, 1.0), 45.0),
+    )));
+
+    // camera options
+    let cam = Camera::new(
+        Vec3::new(0.0, 1.2, 7.0),
+        Vec3::new(0.0, 0.6, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        30.0,
+        nx as f32 / ny as f32,
+        0.0,
+        7.0,
+    );
+
+    (IntersectList::new(list), LightList::new(Vec::new()), cam)
+}
+
+pub fn thinfilm_scene(nx: u32, ny: u32) -> (IntersectList, LightList, Camera) {
+    // iridescent soap bubbles, an oil slick on water and anodized metal
+    //
+    let mut list: Vec<Box<dyn IntersectEvent>> = Vec::with_capacity(8);
+
+    // material options
+    let ground = Lambertian::new(Vec3::new(0.05, 0.05, 0.05));
+    let clear = Vec3::new(1.0, 1.0, 1.0);
+    let oil_slick = Dielectric::new(1.33, 0.0, clear).with_thin_film(450.0, 1.5);
+    let anodized = Conductor::aluminium(0.2).with_thin_film(320.0, 2.0);
+
+    // create base with a puddle of water covered by oil
+    list.push(Box::new(Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        ground,
+    )));
+    list.push(Box::new(Disk::new(
+        Vec3::new(0.0, 0.01, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        2.5,
+        oil_slick,
+    )));
+
+    // create soap bubbles of varying film thickness; a bubble is a soap film
+    // enclosing air
+    for (center, radius, thickness) in [
+        (Vec3::new(-1.2, 1.2, 0.3), 0.6, 300.0),
+        (Vec3::new(0.3, 1.5, -0.6), 0.45, 500.0),
+        (Vec3::new(1.3, 1.0, 0.5), 0.35, 700.0),
+    ] {
+        let bubble = Dielectric::new(1.0, 0.0, clear).with_thin_film(thickness, 1.33);
+        list.push(Box::new(Sphere::new(center, radius, bubble)));
+    }
+
+    // create anodized metal sphere
+    list.push(Box::new(Sphere::new(
+        Vec3::new(0.0, 0.5, 1.2),
+        0.5,
+        anodized,
+    )));
+
+    // camera options
+    let cam = Camera::new(
+        Vec3::new(0.0, 2.5, 7.0),
+        Vec3::new(0.0, 0.8, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        35.0,
+        nx as f32 / ny as f32,
+        0.0,
+        7.0,
+    );
+
+    (IntersectList::new(list), LightList::new(Vec::new()), cam)
 }
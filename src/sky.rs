@@ -0,0 +1,212 @@
+use std::f32;
+
+use crate::light::DirectionalLight;
+use crate::spectrum;
+use crate::vec::Vec3;
+
+// irradiance of the sun above the atmosphere, relative to the sky radiance
+// returned by `Preetham`
+const SUN_IRRADIANCE: f32 = 4.0;
+
+// angular diameter of the sun (in degrees)
+const SUN_ANGULAR_DIAMETER: f32 = 0.53;
+
+// the luminance of the sky (in kcd/m^2) is scaled by `SKY_SCALE`
+const SKY_SCALE: f32 = 0.05;
+
+pub trait Environment {
+    // radiance arriving along rays escaping the scene in `direction`
+    fn radiance(&self, direction: Vec3) -> Vec3;
+}
+
+pub struct Gradient;
+
+impl Environment for Gradient {
+    fn radiance(&self, direction: Vec3) -> Vec3 {
+        // linear interpolation of blue and white based on y-coordinate
+        let t = 0.5 * (direction.unit().y + 1.0);
+        Vec3::new(1.0, 1.0, 1.0) * (1.0 - t) + Vec3::new(0.5, 0.7, 1.0) * t
+    }
+}
+
+pub struct Uniform {
+    pub radiance: Vec3,
+}
+
+impl Uniform {
+    pub fn new(radiance: Vec3) -> Uniform {
+        // environment of constant radiance in all directions
+        Uniform { radiance }
+    }
+}
+
+impl Environment for Uniform {
+    fn radiance(&self, _direction: Vec3) -> Vec3 {
+        self.radiance
+    }
+}
+
+fn perez(coefficients: [f32; 5], cos_theta: f32, gamma: f32) -> f32 {
+    // perez sky luminance distribution for a view direction at zenith angle
+    // theta and angle `gamma` to the sun
+    let [a, b, c, d, e] = coefficients;
+    let cos_gamma = gamma.cos();
+    (1.0 + a * (b / cos_theta.max(1e-3)).exp())
+        * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+}
+
+pub struct Preetham {
+    pub sun_direction: Vec3,
+    pub turbidity: f32,
+    coefficients: [[f32; 5]; 3],
+    zenith: Vec3,
+    ground: Vec3,
+}
+
+impl Preetham {
+    pub fn new(elevation: f32, azimuth: f32, turbidity: f32, ground_albedo: Vec3) -> Preetham {
+        // construct a clear daylight sky (Preetham et al., "A Practical
+        // Analytic Model for Daylight")
+        //   :elevation:     elevation of the sun above the horizon (in degrees)
+        //   :azimuth:       azimuth of the sun from the z axis towards the x
+        //                   axis (in degrees)
+        //   :turbidity:     haziness of the atmosphere in [2, 10]; 2 is a
+        //                   very clear sky
+        //   :ground_albedo: reflectance of the ground below the horizon
+        let t = turbidity.clamp(2.0, 10.0);
+        let (elevation, azimuth) = (elevation.to_radians(), azimuth.to_radians());
+        let sun_direction = Vec3::new(
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            elevation.cos() * azimuth.cos(),
+        );
+        let theta_s = f32::consts::FRAC_PI_2 - elevation.max(0.0);
+
+        // distribution coefficients of the luminance Y and chromaticities x, y
+        let coefficients = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        // luminance and chromaticities at the zenith
+        let chi = (4.0 / 9.0 - t / 120.0) * (f32::consts::PI - 2.0 * theta_s);
+        let zenith_y = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let (s, s2, s3) = (theta_s, theta_s * theta_s, theta_s * theta_s * theta_s);
+        let zenith_x = t * t * (0.00166 * s3 - 0.00375 * s2 + 0.00209 * s)
+            + t * (-0.02903 * s3 + 0.06377 * s2 - 0.03202 * s + 0.00394)
+            + (0.11693 * s3 - 0.21196 * s2 + 0.06052 * s + 0.25886);
+        let zenith_yc = t * t * (0.00275 * s3 - 0.00610 * s2 + 0.00317 * s)
+            + t * (-0.04214 * s3 + 0.08970 * s2 - 0.04153 * s + 0.00516)
+            + (0.15346 * s3 - 0.26756 * s2 + 0.06670 * s + 0.26688);
+
+        // the perez function is normalized by its value at the zenith
+        let cos_s = theta_s.cos();
+        let zenith = Vec3::new(
+            zenith_y.max(0.0) / perez(coefficients[0], 1.0, theta_s),
+            zenith_x / perez(coefficients[1], 1.0, theta_s),
+            zenith_yc / perez(coefficients[2], 1.0, theta_s),
+        );
+        let mut sky = Preetham {
+            sun_direction,
+            turbidity: t,
+            coefficients,
+            zenith,
+            ground: Vec3::new(0.0, 0.0, 0.0),
+        };
+
+        // the ground is lit by the sky and the sun and reflects diffusely; the
+        // irradiance from the sky is integrated numerically
+        let (n_theta, n_phi) = (32, 64);
+        let d_theta = f32::consts::FRAC_PI_2 / n_theta as f32;
+        let d_phi = 2.0 * f32::consts::PI / n_phi as f32;
+        let mut irradiance = match sky.sun() {
+            Some(sun) => sun.irradiance * cos_s,
+            None => Vec3::new(0.0, 0.0, 0.0),
+        };
+        for i in 0..n_theta {
+            let theta = (i as f32 + 0.5) * d_theta;
+            for j in 0..n_phi {
+                let phi = (j as f32 + 0.5) * d_phi;
+                let direction = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.cos(),
+                    theta.sin() * phi.sin(),
+                );
+                irradiance +=
+                    sky.radiance(direction) * (theta.cos() * theta.sin() * d_theta * d_phi);
+            }
+        }
+        sky.ground = ground_albedo * irradiance / f32::consts::PI;
+        sky
+    }
+
+    pub fn sun(&self) -> Option<DirectionalLight> {
+        // the sun as seen through the atmosphere: its light is attenuated by
+        // rayleigh scattering and scattering by aerosols, depending on the
+        // turbidity, over the relative optical air mass along its direction;
+        // none once the sun has set
+        if self.sun_direction.y <= 0.0 {
+            return None;
+        }
+        let elevation = self.sun_direction.y.clamp(0.0, 1.0).asin().to_degrees();
+        let air_mass =
+            1.0 / (elevation.to_radians().sin() + 0.50572 * (elevation + 6.07995).powf(-1.6364));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        let transmittance = |lambda: f32| {
+            // wavelength `lambda` in micrometers
+            let rayleigh = 0.008735 * lambda.powf(-4.08);
+            let aerosol = beta * lambda.powf(-1.3);
+            (-air_mass * (rayleigh + aerosol)).exp()
+        };
+        let color = Vec3::new(
+            transmittance(0.630),
+            transmittance(0.532),
+            transmittance(0.465),
+        );
+        Some(DirectionalLight::new(
+            -self.sun_direction,
+            color * SUN_IRRADIANCE,
+            SUN_ANGULAR_DIAMETER,
+        ))
+    }
+}
+
+impl Environment for Preetham {
+    fn radiance(&self, direction: Vec3) -> Vec3 {
+        // sky radiance above the horizon and the light reflected by the
+        // ground below it
+        let direction = direction.unit();
+        if direction.y < 0.0 {
+            return self.ground;
+        }
+        let gamma = direction.dot(self.sun_direction).clamp(-1.0, 1.0).acos();
+        let luminance = self.zenith.x * perez(self.coefficients[0], direction.y, gamma);
+        let x = self.zenith.y * perez(self.coefficients[1], direction.y, gamma);
+        let y = self.zenith.z * perez(self.coefficients[2], direction.y, gamma);
+        if y <= 0.0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        let xyz = Vec3::new(x / y, 1.0, (1.0 - x - y) / y) * (luminance * SKY_SCALE);
+        let rgb = spectrum::xyz_to_rgb(xyz);
+        Vec3::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0))
+    }
+}
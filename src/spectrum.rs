@@ -0,0 +1,115 @@
+use crate::ray::Ray;
+use crate::vec::Vec3;
+
+// visible range of wavelengths (in nm) sampled in spectral mode
+pub const LAMBDA_MIN: f32 = 360.0;
+pub const LAMBDA_MAX: f32 = 830.0;
+
+// wavelengths (in nm) representing the red, green and blue channels in RGB
+// mode for wavelength dependent effects
+const RGB_WAVELENGTHS: Vec3 = Vec3 {
+    x: 630.0,
+    y: 532.0,
+    z: 465.0,
+};
+
+// integrals of the color matching functions over the visible range, i.e. the
+// XYZ color of the equal-energy spectrum
+const WHITE_XYZ: Vec3 = Vec3 {
+    x: 106.766,
+    y: 106.922,
+    z: 106.875,
+};
+
+fn gaussian(lambda: f32, mu: f32, sigma_below: f32, sigma_above: f32) -> f32 {
+    // piecewise gaussian with different widths below and above the mean
+    let sigma = if lambda < mu {
+        sigma_below
+    } else {
+        sigma_above
+    };
+    (-0.5 * ((lambda - mu) / sigma).powi(2)).exp()
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+pub fn cie_xyz(lambda: f32) -> Vec3 {
+    // CIE 1931 color matching functions at wavelength `lambda` (Wyman et al.,
+    // "Simple Analytic Approximations to the CIE XYZ Color Matching Functions")
+    Vec3::new(
+        1.056 * gaussian(lambda, 599.8, 37.9, 31.0) + 0.362 * gaussian(lambda, 442.0, 16.0, 26.7)
+            - 0.065 * gaussian(lambda, 501.1, 20.4, 26.2),
+        0.821 * gaussian(lambda, 568.8, 46.9, 40.5) + 0.286 * gaussian(lambda, 530.9, 16.3, 31.1),
+        1.217 * gaussian(lambda, 437.0, 11.8, 36.0) + 0.681 * gaussian(lambda, 459.0, 26.0, 13.8),
+    )
+}
+
+pub fn xyz_to_rgb(xyz: Vec3) -> Vec3 {
+    // convert XYZ to linear sRGB
+    Vec3::new(
+        3.240_454 * xyz.x - 1.537_139 * xyz.y - 0.498_531 * xyz.z,
+        -0.969_266 * xyz.x + 1.876_011 * xyz.y + 0.041_556 * xyz.z,
+        0.055_643 * xyz.x - 0.204_026 * xyz.y + 1.057_225 * xyz.z,
+    )
+}
+
+pub fn sample_hero(u: f32) -> f32 {
+    // sample a hero wavelength uniformly over the visible range
+    LAMBDA_MIN + u * (LAMBDA_MAX - LAMBDA_MIN)
+}
+
+pub fn wavelengths(hero: f32) -> Vec3 {
+    // the wavelengths carried by a path: the hero wavelength and two
+    // wavelengths rotated by a third of the visible range from it
+    let range = LAMBDA_MAX - LAMBDA_MIN;
+    let rotate = |i: f32| LAMBDA_MIN + (hero - LAMBDA_MIN + i * range / 3.0) % range;
+    Vec3::new(hero, rotate(1.0), rotate(2.0))
+}
+
+pub fn channel_wavelengths(r: &Ray) -> Vec3 {
+    // wavelengths represented by the channels of colors on the path of `r`
+    match r.wavelength {
+        Some(hero) => wavelengths(hero),
+        None => RGB_WAVELENGTHS,
+    }
+}
+
+pub fn upsample(rgb: Vec3, wavelengths: Vec3) -> Vec3 {
+    // evaluate a smooth spectrum with color `rgb` at `wavelengths`; the
+    // spectrum blends three basis functions covering the blue, green and red
+    // ends of the visible range which sum to one, so white upsamples to the
+    // flat spectrum and reflectances in [0, 1] remain in [0, 1]
+    let evaluate = |lambda: f32| {
+        let blue = 1.0 - smoothstep(480.0, 520.0, lambda);
+        let red = smoothstep(570.0, 610.0, lambda);
+        rgb.x * red + rgb.y * (1.0 - blue - red) + rgb.z * blue
+    };
+    Vec3::new(
+        evaluate(wavelengths.x),
+        evaluate(wavelengths.y),
+        evaluate(wavelengths.z),
+    )
+}
+
+pub fn color(rgb: Vec3, r: &Ray) -> Vec3 {
+    // represent color `rgb` for the path of `r`: unchanged in RGB mode, or
+    // upsampled to the wavelengths of the path in spectral mode
+    match r.wavelength {
+        Some(hero) => upsample(rgb, wavelengths(hero)),
+        None => rgb,
+    }
+}
+
+pub fn to_rgb(values: Vec3, wavelengths: Vec3) -> Vec3 {
+    // estimate the XYZ color of a spectrum from its `values` at the uniformly
+    // sampled `wavelengths` and convert it to linear sRGB, white balanced so
+    // that the flat spectrum maps to white
+    let xyz = cie_xyz(wavelengths.x) * values.x
+        + cie_xyz(wavelengths.y) * values.y
+        + cie_xyz(wavelengths.z) * values.z;
+    let xyz = xyz * ((LAMBDA_MAX - LAMBDA_MIN) / 3.0);
+    xyz_to_rgb(xyz) / xyz_to_rgb(WHITE_XYZ)
+}
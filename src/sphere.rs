@@ -1,8 +1,65 @@
+use std::f32;
+
+use crate::aabb::Aabb;
 use crate::intersect::{IntersectEvent, IntersectRecord};
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::sampler::Sampler;
 use crate::vec::Vec3;
 
+fn sphere_uv(normal: Vec3) -> (f32, f32) {
+    // return surface coordinates of the point on the unit sphere with `normal`
+    //   :u: angle around the y-axis from x = -1, scaled to [0, 1]
+    //   :v: angle from y = -1 to y = +1, scaled to [0, 1]
+    let phi = (-normal.z).atan2(normal.x) + f32::consts::PI;
+    let theta = (-normal.y).clamp(-1.0, 1.0).acos();
+    (phi / (2.0 * f32::consts::PI), theta / f32::consts::PI)
+}
+
+fn sphere_intersect<'a>(
+    center: Vec3,
+    radius: f32,
+    material: &'a dyn Material,
+    r: &Ray,
+    t_min: f32,
+    t_max: f32,
+) -> Option<IntersectRecord<'a>> {
+    // solution to the quadratic equation:
+    //   t*t*dot(b, b)+ 2*t*dot(b, a-c) + dot(a-c, a-c) - r*r = 0
+    let oc = r.origin - center;
+    let a = r.direction.dot(r.direction);
+    let b = oc.dot(r.direction);
+    let c = oc.dot(oc) - radius * radius;
+    let discriminant = b * b - a * c;
+    if discriminant > 0.0 {
+        // check both solutions to the quadratic equation
+        for &t in [
+            (-b - discriminant.sqrt()) / a,
+            (-b + discriminant.sqrt()) / a,
+        ]
+        .iter()
+        {
+            if t_min < t && t < t_max {
+                let p = r.point_at_parameter(t);
+                let normal = (p - center) / radius;
+                let (u, v) = sphere_uv(normal);
+                // derivative of the point with respect to `u`, vanishing at
+                // the poles
+                let tangent = Vec3::new(normal.z, 0.0, -normal.x);
+                return Some(
+                    IntersectRecord::new(t, p, normal, u, v, material).with_tangent(tangent),
+                );
+            }
+        }
+    }
+    None
+}
+
+fn sphere_bounding_box(center: Vec3, radius: f32) -> Aabb {
+    let extent = Vec3::new(radius.abs(), radius.abs(), radius.abs());
+    Aabb::new(center - extent, center + extent)
+}
+
 pub struct Sphere<M: Material> {
     pub center: Vec3,
     pub radius: f32,
@@ -20,29 +77,83 @@ impl<M: Material> Sphere<M> {
 }
 
 impl<M: Material> IntersectEvent for Sphere<M> {
-    fn intersect(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<IntersectRecord> {
-        // solution to the quadratic equation:
-        //   t*t*dot(b, b)+ 2*t*dot(b, a-c) + dot(a-c, a-c) - r*r = 0
-        let oc = r.origin - self.center;
-        let a = r.direction.dot(r.direction);
-        let b = oc.dot(r.direction);
-        let c = oc.dot(oc) - self.radius * self.radius;
-        let discriminant = b * b - a * c;
-        if discriminant > 0.0 {
-            // check both solutions to the quadratic equation
-            let t = (-b - discriminant.sqrt()) / a;
-            if t_min < t && t < t_max {
-                let p = r.point_at_parameter(t);
-                let normal = (p - self.center) / self.radius;
-                return Some(IntersectRecord::new(t, p, normal, &self.material));
-            }
-            let t = (-b + discriminant.sqrt()) / a;
-            if t_min < t && t < t_max {
-                let p = r.point_at_parameter(t);
-                let normal = (p - self.center) / self.radius;
-                return Some(IntersectRecord::new(t, p, normal, &self.material));
-            }
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
+        sphere_intersect(self.center, self.radius, &self.material, r, t_min, t_max)
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        Some(sphere_bounding_box(self.center, self.radius))
+    }
+}
+
+pub struct MovingSphere<M: Material> {
+    pub keyframes: Vec<(f32, Vec3)>,
+    pub radius: f32,
+    pub material: M,
+}
+
+impl<M: Material> MovingSphere<M> {
+    pub fn new(mut keyframes: Vec<(f32, Vec3)>, radius: f32, material: M) -> MovingSphere<M> {
+        // construct sphere whose center moves linearly between `keyframes`
+        // of (time, center); the center is held before the first and after
+        // the last keyframe
+        assert!(!keyframes.is_empty(), "moving sphere requires a keyframe");
+        keyframes.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        MovingSphere {
+            keyframes,
+            radius,
+            material,
         }
-        None
+    }
+
+    pub fn center(&self, time: f32) -> Vec3 {
+        // return the center of the sphere at `time`
+        let (first, last) = (self.keyframes[0], self.keyframes[self.keyframes.len() - 1]);
+        if time <= first.0 {
+            return first.1;
+        }
+        if time >= last.0 {
+            return last.1;
+        }
+        let i = self.keyframes.iter().position(|k| k.0 > time).unwrap();
+        let (t0, c0) = self.keyframes[i - 1];
+        let (t1, c1) = self.keyframes[i];
+        c0 + (c1 - c0) * ((time - t0) / (t1 - t0))
+    }
+}
+
+impl<M: Material> IntersectEvent for MovingSphere<M> {
+    fn intersect(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<IntersectRecord<'_>> {
+        sphere_intersect(
+            self.center(r.time),
+            self.radius,
+            &self.material,
+            r,
+            t_min,
+            t_max,
+        )
+    }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+        // the center moves along line segments between keyframes, so the
+        // bounds at the interval ends and at the keyframes inside the interval
+        // cover the full motion
+        let bbox = self.keyframes.iter().filter(|k| t0 < k.0 && k.0 < t1).fold(
+            sphere_bounding_box(self.center(t0), self.radius),
+            |bbox, k| bbox.surrounding(&sphere_bounding_box(k.1, self.radius)),
+        );
+        Some(bbox.surrounding(&sphere_bounding_box(self.center(t1), self.radius)))
     }
 }
@@ -0,0 +1,90 @@
+use crate::noise::Perlin;
+use crate::vec::Vec3;
+
+pub trait Texture {
+    // scalar value of the texture at surface coordinates (`u`, `v`) of the
+    // point `p`
+    fn value(&self, u: f32, v: f32, p: Vec3) -> f32;
+}
+
+impl Texture for f32 {
+    fn value(&self, _u: f32, _v: f32, _p: Vec3) -> f32 {
+        // constant texture
+        *self
+    }
+}
+
+pub struct NoiseTexture {
+    pub perlin: Perlin,
+    pub frequency: f32,
+    pub octaves: u32,
+    pub threshold: f32,
+    pub softness: f32,
+}
+
+impl NoiseTexture {
+    pub fn new(perlin: Perlin, frequency: f32, octaves: u32) -> NoiseTexture {
+        // construct a texture of fractal noise over space
+        //   :perlin:    noise generator
+        //   :frequency: frequency of the first octave (per unit length)
+        //   :octaves:   number of octaves
+        NoiseTexture {
+            perlin,
+            frequency,
+            octaves,
+            threshold: 0.5,
+            softness: 0.5,
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: f32, softness: f32) -> NoiseTexture {
+        // remap the noise to a pattern of patches covering the points where
+        // it exceeds `threshold`, with edges blurred by `softness`
+        self.threshold = threshold;
+        self.softness = softness.max(1e-4);
+        self
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f32, _v: f32, p: Vec3) -> f32 {
+        let noise = 0.5 + 0.5 * self.perlin.fbm(p * self.frequency, self.octaves);
+        let t = ((noise - self.threshold + self.softness) / (2.0 * self.softness)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+pub struct GridTexture {
+    pub cells_u: f32,
+    pub cells_v: f32,
+    pub width: f32,
+}
+
+impl GridTexture {
+    pub fn new(cells_u: f32, cells_v: f32, width: f32) -> GridTexture {
+        // construct a texture of grid lines over the surface coordinates,
+        // one within the lines and zero in the cells between them
+        //   :cells_u: number of cells along u
+        //   :cells_v: number of cells along v
+        //   :width:   width of the lines relative to the cells
+        GridTexture {
+            cells_u,
+            cells_v,
+            width,
+        }
+    }
+}
+
+impl Texture for GridTexture {
+    fn value(&self, u: f32, v: f32, _p: Vec3) -> f32 {
+        let on_line = |x: f32| {
+            let x = x - x.floor();
+            x < 0.5 * self.width || x > 1.0 - 0.5 * self.width
+        };
+        if on_line(u * self.cells_u) || on_line(v * self.cells_v) {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::f32;
+use std::ops;
+
+use crate::vec::Vec3;
+
+#[derive(Debug, Copy, Clone)]
+struct Complex {
+    re: f32,
+    im: f32,
+}
+
+impl Complex {
+    fn new(re: f32, im: f32) -> Complex {
+        Complex { re, im }
+    }
+
+    fn real(re: f32) -> Complex {
+        Complex::new(re, 0.0)
+    }
+
+    fn norm2(self) -> f32 {
+        self.re * self.re + self.im * self.im
+    }
+
+    fn sqrt(self) -> Complex {
+        // principal square root
+        let r = self.norm2().sqrt();
+        let re = (0.5 * (r + self.re)).max(0.0).sqrt();
+        let im = (0.5 * (r - self.re)).max(0.0).sqrt();
+        Complex::new(re, if self.im < 0.0 { -im } else { im })
+    }
+
+    fn exp_i(self) -> Complex {
+        // e^(i * self)
+        let scale = (-self.im).exp();
+        Complex::new(scale * self.re.cos(), scale * self.re.sin())
+    }
+}
+
+impl ops::Add<Complex> for Complex {
+    type Output = Complex;
+
+    fn add(self, c: Complex) -> Complex {
+        Complex::new(self.re + c.re, self.im + c.im)
+    }
+}
+
+impl ops::Sub<Complex> for Complex {
+    type Output = Complex;
+
+    fn sub(self, c: Complex) -> Complex {
+        Complex::new(self.re - c.re, self.im - c.im)
+    }
+}
+
+impl ops::Mul<Complex> for Complex {
+    type Output = Complex;
+
+    fn mul(self, c: Complex) -> Complex {
+        Complex::new(
+            self.re * c.re - self.im * c.im,
+            self.re * c.im + self.im * c.re,
+        )
+    }
+}
+
+impl ops::Div<Complex> for Complex {
+    type Output = Complex;
+
+    fn div(self, c: Complex) -> Complex {
+        let d = c.norm2();
+        Complex::new(
+            (self.re * c.re + self.im * c.im) / d,
+            (self.im * c.re - self.re * c.im) / d,
+        )
+    }
+}
+
+fn amplitudes(n_i: Complex, cos_i: Complex, n_t: Complex, cos_t: Complex) -> (Complex, Complex) {
+    // fresnel amplitude reflection coefficients of an interface for s and p
+    // polarized light
+    let s = (n_i * cos_i - n_t * cos_t) / (n_i * cos_i + n_t * cos_t);
+    let p = (n_t * cos_i - n_i * cos_t) / (n_t * cos_i + n_i * cos_t);
+    (s, p)
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ThinFilm {
+    pub thickness: f32,
+    pub ior: f32,
+}
+
+impl ThinFilm {
+    pub fn new(thickness: f32, ior: f32) -> ThinFilm {
+        // construct a thin transparent coating
+        //   :thickness: thickness of the film (in nm)
+        //   :ior:       refractive index of the film
+        ThinFilm { thickness, ior }
+    }
+
+    fn reflectance_at(&self, cos_i: f32, n1: f32, eta: f32, k: f32, lambda: f32) -> f32 {
+        // reflectance at wavelength `lambda` (in nm) of the film between an
+        // ambient medium of index `n1` and a substrate of complex index
+        // eta + ik, summing the light reflected inside the film (airy)
+        let sin2 = 1.0 - cos_i * cos_i;
+        let n1 = Complex::real(n1);
+        let n2 = Complex::real(self.ior);
+        let n3 = Complex::new(eta, k);
+        let one = Complex::real(1.0);
+        let cos1 = Complex::real(cos_i);
+        let cos2 = (one - Complex::real(sin2) * (n1 * n1) / (n2 * n2)).sqrt();
+        let cos3 = (one - Complex::real(sin2) * (n1 * n1) / (n3 * n3)).sqrt();
+        let (r12_s, r12_p) = amplitudes(n1, cos1, n2, cos2);
+        let (r23_s, r23_p) = amplitudes(n2, cos2, n3, cos3);
+
+        // phase difference between successive reflections inside the film
+        let delta =
+            Complex::real(4.0 * f32::consts::PI * self.ior * self.thickness / lambda) * cos2;
+        let phase = delta.exp_i();
+        let airy =
+            |r12: Complex, r23: Complex| ((r12 + r23 * phase) / (one + r12 * r23 * phase)).norm2();
+        (0.5 * (airy(r12_s, r23_s) + airy(r12_p, r23_p))).clamp(0.0, 1.0)
+    }
+
+    pub fn reflectance(&self, cos_i: f32, n1: f32, eta: Vec3, k: Vec3, wavelengths: Vec3) -> Vec3 {
+        // reflectance of the coated substrate for each channel, evaluated at
+        // the wavelength representing the channel
+        let cos_i = cos_i.clamp(0.0, 1.0);
+        Vec3::new(
+            self.reflectance_at(cos_i, n1, eta.x, k.x, wavelengths.x),
+            self.reflectance_at(cos_i, n1, eta.y, k.y, wavelengths.y),
+            self.reflectance_at(cos_i, n1, eta.z, k.z, wavelengths.z),
+        )
+    }
+}
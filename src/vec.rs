@@ -1,6 +1,6 @@
 use std::ops;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Vec3 {
     pub x: f32,
     pub y: f32,
@@ -36,6 +36,10 @@ impl Vec3 {
         let length = self.length();
         Vec3::new(self.x / length, self.y / length, self.z / length)
     }
+
+    pub fn exp(&self) -> Vec3 {
+        Vec3::new(self.x.exp(), self.y.exp(), self.z.exp())
+    }
 }
 
 impl ops::Add<Vec3> for Vec3 {
@@ -122,3 +126,358 @@ impl ops::DivAssign<f32> for Vec3 {
         *self = Vec3::new(self.x / t, self.y / t, self.z / t);
     }
 }
+
+#[derive(Debug, Copy, Clone)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn new(n: Vec3) -> Onb {
+        // construct an orthonormal basis whose `w` axis is the unit vector `n`
+        // (Duff et al., "Building an Orthonormal Basis, Revisited")
+        let sign = 1.0_f32.copysign(n.z);
+        let a = -1.0 / (sign + n.z);
+        let b = n.x * n.y * a;
+        Onb {
+            u: Vec3::new(1.0 + sign * n.x * n.x * a, sign * b, -sign * n.x),
+            v: Vec3::new(b, sign + n.y * n.y * a, -n.y),
+            w: n,
+        }
+    }
+
+    pub fn from_tangent(n: Vec3, t: Vec3) -> Onb {
+        // construct an orthonormal basis whose `w` axis is the unit vector `n`
+        // and whose `u` axis is `t` projected onto the plane normal to `n`;
+        // falls back to `Onb::new` if `t` is (nearly) parallel to `n`
+        let u = t - n * t.dot(n);
+        if u.squared_length() < 1e-12 {
+            return Onb::new(n);
+        }
+        let u = u.unit();
+        Onb {
+            u,
+            v: n.cross(u),
+            w: n,
+        }
+    }
+
+    pub fn to_world(self, a: Vec3) -> Vec3 {
+        // transform `a` from local coordinates into world coordinates
+        self.u * a.x + self.v * a.y + self.w * a.z
+    }
+
+    pub fn to_local(self, a: Vec3) -> Vec3 {
+        // transform `a` from world coordinates into local coordinates
+        Vec3::new(a.dot(self.u), a.dot(self.v), a.dot(self.w))
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Matrix4 {
+    pub m: [[f32; 4]; 4],
+}
+
+impl Matrix4 {
+    pub fn new(m: [[f32; 4]; 4]) -> Matrix4 {
+        // construct matrix from rows `m`
+        Matrix4 { m }
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let mut t = [[0.0; 4]; 4];
+        for (i, row) in t.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        Matrix4::new(t)
+    }
+
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        // apply the matrix to point `p` (w = 1) including the projective divide
+        let m = &self.m;
+        let x = m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3];
+        let y = m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3];
+        let z = m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3];
+        let w = m[3][0] * p.x + m[3][1] * p.y + m[3][2] * p.z + m[3][3];
+        if w == 1.0 {
+            Vec3::new(x, y, z)
+        } else {
+            Vec3::new(x, y, z) / w
+        }
+    }
+
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        // apply the matrix to direction `v` (w = 0)
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+}
+
+impl ops::Mul<Matrix4> for Matrix4 {
+    type Output = Matrix4;
+    fn mul(self, b: Matrix4) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * b.m[k][j]).sum();
+            }
+        }
+        Matrix4::new(m)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Transform {
+    pub m: Matrix4,
+    pub m_inv: Matrix4,
+}
+
+impl Transform {
+    pub fn translate(delta: Vec3) -> Transform {
+        let m = Matrix4::new([
+            [1.0, 0.0, 0.0, delta.x],
+            [0.0, 1.0, 0.0, delta.y],
+            [0.0, 0.0, 1.0, delta.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let m_inv = Matrix4::new([
+            [1.0, 0.0, 0.0, -delta.x],
+            [0.0, 1.0, 0.0, -delta.y],
+            [0.0, 0.0, 1.0, -delta.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        Transform { m, m_inv }
+    }
+
+    pub fn scale(s: Vec3) -> Transform {
+        let m = Matrix4::new([
+            [s.x, 0.0, 0.0, 0.0],
+            [0.0, s.y, 0.0, 0.0],
+            [0.0, 0.0, s.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let m_inv = Matrix4::new([
+            [1.0 / s.x, 0.0, 0.0, 0.0],
+            [0.0, 1.0 / s.y, 0.0, 0.0],
+            [0.0, 0.0, 1.0 / s.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        Transform { m, m_inv }
+    }
+
+    pub fn rotate(axis: Vec3, degrees: f32) -> Transform {
+        // rotation by `degrees` counter-clockwise about `axis`
+        let a = axis.unit();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let m = Matrix4::new([
+            [
+                a.x * a.x + (1.0 - a.x * a.x) * cos,
+                a.x * a.y * (1.0 - cos) - a.z * sin,
+                a.x * a.z * (1.0 - cos) + a.y * sin,
+                0.0,
+            ],
+            [
+                a.x * a.y * (1.0 - cos) + a.z * sin,
+                a.y * a.y + (1.0 - a.y * a.y) * cos,
+                a.y * a.z * (1.0 - cos) - a.x * sin,
+                0.0,
+            ],
+            [
+                a.x * a.z * (1.0 - cos) - a.y * sin,
+                a.y * a.z * (1.0 - cos) + a.x * sin,
+                a.z * a.z + (1.0 - a.z * a.z) * cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        // the inverse of a rotation is its transpose
+        Transform {
+            m,
+            m_inv: m.transpose(),
+        }
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Transform {
+            m: self.m_inv,
+            m_inv: self.m,
+        }
+    }
+
+    pub fn point(&self, p: Vec3) -> Vec3 {
+        self.m.transform_point(p)
+    }
+
+    pub fn vector(&self, v: Vec3) -> Vec3 {
+        self.m.transform_vector(v)
+    }
+
+    pub fn normal(&self, n: Vec3) -> Vec3 {
+        // normals transform by the inverse transpose to stay perpendicular to
+        // the transformed surface
+        self.m_inv.transpose().transform_vector(n)
+    }
+}
+
+impl ops::Mul<Transform> for Transform {
+    type Output = Transform;
+    fn mul(self, t: Transform) -> Transform {
+        // compose transforms; `t` is applied first
+        Transform {
+            m: self.m * t.m,
+            m_inv: t.m_inv * self.m_inv,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Quaternion {
+    pub v: Vec3,
+    pub w: f32,
+}
+
+impl Quaternion {
+    pub fn from_axis_angle(axis: Vec3, degrees: f32) -> Quaternion {
+        // unit quaternion rotating by `degrees` counter-clockwise about `axis`
+        let (sin, cos) = (degrees.to_radians() / 2.0).sin_cos();
+        Quaternion {
+            v: axis.unit() * sin,
+            w: cos,
+        }
+    }
+
+    pub fn dot(&self, q: Quaternion) -> f32 {
+        self.v.dot(q.v) + self.w * q.w
+    }
+
+    pub fn slerp(self, q: Quaternion, t: f32) -> Quaternion {
+        // spherical linear interpolation along the shorter arc from `self` to `q`
+        let mut cos_theta = self.dot(q);
+        let q = if cos_theta < 0.0 {
+            cos_theta = -cos_theta;
+            Quaternion { v: -q.v, w: -q.w }
+        } else {
+            q
+        };
+        let (a, b) = if cos_theta > 0.9995 {
+            // nearly parallel; linear interpolation is accurate and stable
+            (1.0 - t, t)
+        } else {
+            let theta = cos_theta.acos();
+            let sin_theta = theta.sin();
+            (
+                ((1.0 - t) * theta).sin() / sin_theta,
+                (t * theta).sin() / sin_theta,
+            )
+        };
+        let v = self.v * a + q.v * b;
+        let w = self.w * a + q.w * b;
+        let length = (v.squared_length() + w * w).sqrt();
+        Quaternion {
+            v: v / length,
+            w: w / length,
+        }
+    }
+
+    pub fn to_transform(self) -> Transform {
+        let (x, y, z, w) = (self.v.x, self.v.y, self.v.z, self.w);
+        let m = Matrix4::new([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - z * w),
+                2.0 * (x * z + y * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + z * w),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - x * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - y * w),
+                2.0 * (y * z + x * w),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        Transform {
+            m,
+            m_inv: m.transpose(),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Keyframe {
+    pub time: f32,
+    pub translate: Vec3,
+    pub rotate: Quaternion,
+    pub scale: Vec3,
+}
+
+impl Keyframe {
+    pub fn new(time: f32, translate: Vec3, rotate: Quaternion, scale: Vec3) -> Keyframe {
+        // construct keyframe applying `scale`, then `rotate`, then `translate`
+        Keyframe {
+            time,
+            translate,
+            rotate,
+            scale,
+        }
+    }
+
+    pub fn transform(&self) -> Transform {
+        Transform::translate(self.translate)
+            * self.rotate.to_transform()
+            * Transform::scale(self.scale)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimatedTransform {
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl AnimatedTransform {
+    pub fn new(mut keyframes: Vec<Keyframe>) -> AnimatedTransform {
+        // construct transform interpolated between `keyframes`; translation
+        // and scale are interpolated linearly and rotation spherically
+        assert!(
+            !keyframes.is_empty(),
+            "animated transform requires a keyframe"
+        );
+        keyframes.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+        AnimatedTransform { keyframes }
+    }
+
+    pub fn keyframe(&self, time: f32) -> Keyframe {
+        // return the interpolated keyframe at `time`; the transform is held
+        // before the first and after the last keyframe
+        let (first, last) = (self.keyframes[0], self.keyframes[self.keyframes.len() - 1]);
+        if time <= first.time {
+            return first;
+        }
+        if time >= last.time {
+            return last;
+        }
+        let i = self.keyframes.iter().position(|k| k.time > time).unwrap();
+        let (a, b) = (self.keyframes[i - 1], self.keyframes[i]);
+        let t = (time - a.time) / (b.time - a.time);
+        Keyframe::new(
+            time,
+            a.translate + (b.translate - a.translate) * t,
+            a.rotate.slerp(b.rotate, t),
+            a.scale + (b.scale - a.scale) * t,
+        )
+    }
+}